@@ -0,0 +1,74 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since the unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Parses ages like `30s`, `5m`, `12h`, `90d`, `6w` or `1y` into seconds.
+pub fn parse_age(age: &str) -> Result<u64, String> {
+    let split = age.len() - age.chars().last().map_or(0, char::len_utf8);
+    let (count, unit) = age.split_at(split);
+
+    let count: u64 = count
+        .parse()
+        .map_err(|_| format!("expected a number followed by a unit, got `{age}`"))?;
+
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => return Err(format!("unknown unit in `{age}`, use s, m, h, d, w or y")),
+    };
+
+    count
+        .checked_mul(unit)
+        .ok_or_else(|| format!("age `{age}` is too large"))
+}
+
+/// Formats a duration in seconds like `5m`, `3h` or `2d`, rounding down.
+pub fn format_age(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 => format!("{s}s"),
+        s if s < 60 * 60 => format!("{}m", s / 60),
+        s if s < 24 * 60 * 60 => format!("{}h", s / (60 * 60)),
+        s => format!("{}d", s / (24 * 60 * 60)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ages() {
+        assert_eq!(parse_age("90d"), Ok(90 * 24 * 60 * 60));
+        assert_eq!(parse_age("2w"), Ok(14 * 24 * 60 * 60));
+        assert_eq!(parse_age("1h"), Ok(60 * 60));
+        assert_eq!(parse_age("2s"), Ok(2));
+        assert_eq!(parse_age("5m"), Ok(5 * 60));
+        assert_eq!(parse_age(&format_age(61 * 60)), Ok(60 * 60));
+    }
+
+    #[test]
+    fn formats_ages() {
+        assert_eq!(format_age(59), "59s");
+        assert_eq!(format_age(61 * 60), "1h");
+        assert_eq!(format_age(3 * 24 * 60 * 60 + 5), "3d");
+    }
+
+    #[test]
+    fn rejects_invalid_ages() {
+        assert!(parse_age("90").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3x").is_err());
+        assert!(parse_age("99999999999999999w").is_err());
+        assert!(parse_age("").is_err());
+    }
+}
@@ -0,0 +1,2420 @@
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, IsTerminal, Write};
+use std::iter;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, ExitCode};
+use std::time::Duration;
+
+use clap::{ArgAction, CommandFactory, Parser, Subcommand};
+use regex::Regex;
+use tracing::{debug, error, info, warn};
+
+use crate::age::{format_age, now, parse_age};
+use crate::complete::complete;
+use crate::config::Config;
+use crate::doctor::{
+    backup, check_bookmarks, check_permissions, check_sizes, fix_permissions, readable_content,
+    repair, unreadable_lines,
+};
+use crate::edit::edit;
+use crate::entries::{Entries, EntryFlags};
+use crate::error::{AppError, AppResult};
+use crate::exists::check_all;
+use crate::export::{ExportFormat, export};
+use crate::format::{Format, json};
+use crate::frecency::{frecency, match_weight, sort_by_frecency, visit};
+use crate::git::{origin_name, project_root};
+use crate::guess::{close_matches, guess, list_child_dirs};
+use crate::import::{ImportSource, imported};
+use crate::index_renderer::{FILE, PIN};
+use crate::init::{
+    InitOptions, Prompt, Shell, abbreviations, cd_fallback, init, insert_widget, nu_module, prompt,
+    prompt_config, visit_hook,
+};
+use crate::install::{init_line, install, rc_file, uninstall};
+use crate::last_pick::{last_pick_file, read_last_pick, write_last_pick};
+use crate::logger::LOG_ENV;
+use crate::matching::Matching;
+use crate::merge::merge_into;
+use crate::moved::find_moved;
+use crate::open::open;
+use crate::path_cmp::{same_path, simplify, strip_base};
+use crate::path_map::{PathMap, bookmark_path, expand, expand_home, to_local, to_store};
+use crate::pickers::{Picked, Query, pick_many, pick_many_matching, pick_one, pick_one_last_dim};
+use crate::porcelain::{Porcelain, porcelain};
+use crate::profile::{DEFAULT_PROFILE, profile_dir, profiles};
+use crate::progress::{Interrupt, Progress};
+use crate::project::{project_bookmarks, save_project_bookmark};
+use crate::recent::{read_recent, recent_file, record_visit, restart_count};
+use crate::remote::is_remote;
+use crate::sort::{Sort, move_to, sort};
+use crate::stats::stats;
+use crate::store::{
+    Backend, Bookmark, Store, StoreLock, daemon, open_store, parse_bookmarks, unique_name,
+    validate_tag, validate_value,
+};
+use crate::suggest::suggestions;
+use crate::theme::ColorChoice;
+use crate::undo::Journal;
+use crate::{logger, remote, status, sync, theme, tmux, tui};
+
+#[derive(Parser)]
+#[command(name = "pathmarks")]
+#[command(about = "Path bookmark manager", version)]
+struct Cli {
+    /// Run as if pathmarks was started in this directory
+    #[arg(long, global = true)]
+    cwd: Option<PathBuf>,
+
+    /// Bookmarks file to use instead of the one in the data directory
+    #[arg(long, global = true, env = "PATHMARKS_STORE")]
+    store: Option<PathBuf>,
+
+    /// Use the bookmarks of this profile
+    #[arg(
+        long,
+        global = true,
+        env = "PATHMARKS_PROFILE",
+        conflicts_with = "store"
+    )]
+    profile: Option<String>,
+
+    /// Only print errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print what pathmarks is doing, more with -vv and -vvv
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// When to color the picker and `list`
+    #[arg(long, global = true, value_name = "WHEN", value_enum, default_value_t)]
+    color: ColorChoice,
+
+    #[command(flatten)]
+    matching: Matching,
+
+    #[command(subcommand)]
+    command: Cmd,
+}
+
+#[derive(Subcommand)]
+enum Cmd {
+    Save {
+        /// Name of the bookmark, defaults to org/repo for git repositories
+        #[arg(long)]
+        name: Option<String>,
+        /// Save to the `.pathmarks` file of the enclosing git repository
+        #[arg(long)]
+        project: bool,
+        /// Save the root of the enclosing repository or workspace instead
+        #[arg(long)]
+        root: bool,
+        /// Bookmark this file instead of the current directory
+        #[arg(long, value_name = "PATH", conflicts_with = "root")]
+        file: Option<PathBuf>,
+        /// Tag the bookmark, can be given several times
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Note shown next to the bookmark in the picker
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Bookmark the given paths, relative ones are resolved against the current directory
+    Add {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    Remove {
+        path: Option<String>,
+        /// Remove the closest bookmark when there is no exact match
+        #[arg(long, requires = "path")]
+        force_nearest: bool,
+    },
+    Prune {
+        /// Also remove bookmarks not jumped to for this long, e.g. `90d`
+        #[arg(long, value_parser = parse_age)]
+        unused_for: Option<u64>,
+        /// Print what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+        /// Choose which of the found bookmarks to remove, queued with tab
+        #[arg(long, conflicts_with = "dry_run")]
+        interactive: bool,
+        /// Also look for moved directories below the grandparent, not only
+        /// the parent
+        #[arg(long)]
+        grandparent: bool,
+        /// Keep bookmarks whose check takes longer than this, e.g. `2s` for
+        /// network mounts that may hang
+        #[arg(long, value_parser = parse_age)]
+        timeout: Option<u64>,
+    },
+    List {
+        /// Stable, tab-separated output for scripts
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "v1")]
+        porcelain: Option<Porcelain>,
+        /// Show icons next to bookmarks
+        #[arg(long)]
+        pretty: bool,
+        /// Show the number `goto` jumps to for every bookmark
+        #[arg(long, conflicts_with_all = ["porcelain", "format"])]
+        numbered: bool,
+        /// Output format
+        #[arg(long, value_enum, default_value_t, conflicts_with_all = ["porcelain", "pretty"])]
+        format: Format,
+        /// Only list bookmarks whose absolute path matches this regular expression
+        #[arg(long)]
+        regex: Option<Regex>,
+        /// Only list bookmarks with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Order of the bookmarks
+        #[arg(long, value_enum, default_value_t)]
+        sort: Sort,
+        #[command(flatten)]
+        entries: EntryFlags,
+        /// Also show hidden directories of the current directory
+        #[arg(long)]
+        hidden: bool,
+        /// End every path or porcelain record with a NUL byte instead of a
+        /// newline, for `xargs -0`
+        #[arg(short = '0', long, visible_alias = "null", conflicts_with = "format")]
+        print0: bool,
+        /// Print every path absolute
+        #[arg(long, conflicts_with = "relative")]
+        absolute: bool,
+        /// Print every path relative to the current directory, with `..` where
+        /// needed
+        #[arg(long)]
+        relative: bool,
+    },
+    /// Move a bookmark to another position in the saved order, counting from 1
+    Move {
+        path: String,
+        #[arg(long)]
+        to: usize,
+    },
+    Guess {
+        paths: Vec<String>,
+        /// Choose the best match even when others score about the same,
+        /// instead of asking with the picker
+        #[arg(long)]
+        no_interactive: bool,
+    },
+    /// Complete an argument of `t` for the shell: `api/mi` completes the
+    /// directories below the bookmark matching `api`, anything else the
+    /// bookmarks
+    Complete {
+        #[arg(default_value = "")]
+        partial: String,
+    },
+    /// Print the bookmark best matching a query, without the picker.
+    /// Takes the flags of `zoxide query`.
+    Query {
+        /// Without terms, every bookmark matches, ranked by frecency
+        terms: Vec<String>,
+        /// Print every match, best first
+        #[arg(short, long, conflicts_with = "interactive")]
+        list: bool,
+        /// Print at most this many matches
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+        /// Print the match score before each path
+        #[arg(short, long)]
+        score: bool,
+        /// Stable, tab-separated output for scripts, the same as `list
+        /// --porcelain`
+        #[arg(
+            long,
+            value_enum,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "v1",
+            conflicts_with_all = ["score", "interactive"]
+        )]
+        porcelain: Option<Porcelain>,
+        /// Leave out this directory, e.g. "$PWD"
+        #[arg(long, value_name = "DIR")]
+        exclude: Vec<PathBuf>,
+        /// Choose between the matches with the picker
+        #[arg(short, long)]
+        interactive: bool,
+    },
+    Pick {
+        /// Only offer bookmarks with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Pick several bookmarks with tab, printing one path per line
+        #[arg(long)]
+        multi: bool,
+        /// Also offer recently visited directories that are not bookmarked
+        #[arg(long, conflicts_with = "multi")]
+        recent: bool,
+        #[command(flatten)]
+        entries: EntryFlags,
+        /// Also show hidden directories of the current directory
+        #[arg(long)]
+        hidden: bool,
+        /// Offer directories up to this many levels below the current one
+        /// [default: 1]
+        #[arg(long, value_name = "LEVELS", conflicts_with = "multi")]
+        depth: Option<usize>,
+        /// Open the picker with this text in the search box
+        #[arg(long, value_name = "TEXT", default_value = "")]
+        query: String,
+        /// Pick the only match without opening the picker
+        #[arg(long = "select-1", short = '1')]
+        select_1: bool,
+    },
+    /// Record a visit to a directory, called by the shell on every directory change
+    Visit { path: PathBuf },
+    /// List recently visited directories, most recent first
+    Recent {
+        /// Only list this many
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    /// Print how many bookmarks there are, which are missing and which are
+    /// used most and least
+    Stats {
+        /// Stable, tab-separated output for scripts
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "v1")]
+        porcelain: Option<Porcelain>,
+        #[arg(long, value_enum, default_value_t, conflicts_with = "porcelain")]
+        format: Format,
+    },
+    /// Check the store for duplicates, broken paths, unreadable lines and
+    /// loose permissions
+    Doctor {
+        /// Repair what can be repaired, after backing up the store
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Open a bookmark with an application, the file manager by default
+    Open {
+        query: Vec<String>,
+        /// Name of an opener from the config, or a program to run
+        #[arg(long)]
+        with: Option<String>,
+        /// Use the opener by default for this bookmark
+        #[arg(long, requires = "with")]
+        remember: bool,
+    },
+    /// Run a program in the directory a query resolves to like `guess`, e.g.
+    /// `pathmarks exec api -- git pull`
+    Exec {
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// The program and its arguments
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+    /// Switch to a tmux window named after a bookmark, opened in its
+    /// directory the first time. Picks one without a query.
+    Tmux {
+        query: Vec<String>,
+        /// Use a session instead of a window, attaching to it outside tmux
+        #[arg(long)]
+        session: bool,
+    },
+    /// Print a short mark when the current directory is bookmarked, for the
+    /// prompt, and nothing otherwise
+    Status {
+        /// What to print, with `{name}`, `{icon}` and `{path}` of the bookmark
+        #[arg(long, value_name = "TEMPLATE", default_value = status::FORMAT)]
+        format: String,
+    },
+    /// Print the bookmark in slot N, or else the Nth bookmark, counting from 1
+    /// in the order they were saved
+    #[command(visible_alias = "get")]
+    Goto { index: usize },
+    /// Set the icon shown next to a bookmark, an empty icon removes it
+    Icon { icon: String, path: Option<String> },
+    /// Keep a bookmark at the top of the list and the picker
+    Pin { path: Option<String> },
+    /// Stop keeping a bookmark at the top
+    Unpin { path: Option<String> },
+    /// Add, remove or list tags of bookmarks
+    Tag {
+        #[command(subcommand)]
+        action: TagCmd,
+    },
+    /// Put bookmarks in quick slots 1 to 9, jumped to with `goto`
+    Slot {
+        #[command(subcommand)]
+        action: SlotCmd,
+    },
+    /// Bookmark the directories another jump tool knows about
+    Import {
+        source: ImportSource,
+        /// The export to read, for `import file`
+        #[arg(required_if_eq("source", "file"))]
+        file: Option<PathBuf>,
+        /// Only import directories scored at least this high
+        #[arg(long, default_value_t = 10.0)]
+        min_score: f64,
+        /// Print the directories that would be imported
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Add the bookmarks of another bookmarks file, combining those of the
+    /// same directory
+    Merge {
+        /// A plain text bookmarks file, like a list shared by a team
+        other: PathBuf,
+    },
+    /// Restore the bookmarks from before the last remove, prune, edit or merge
+    Undo {
+        /// Show what can be undone, newest first
+        #[arg(long)]
+        list: bool,
+    },
+    /// Edit the bookmarks in $VISUAL or $EDITOR
+    Edit,
+    /// Keep the bookmarks in memory for other commands until stopped
+    Daemon {
+        /// Hide bookmarks the moment their directory is deleted or renamed,
+        /// until it is back
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Browse, reorder, rename, tag, pin and delete bookmarks on one screen,
+    /// printing the path chosen with enter
+    Ui,
+    /// Share the bookmarks of every profile through a git repository in the
+    /// data directory: commit, merge those of the remote and push
+    Sync {
+        /// Remote to sync with from now on
+        #[arg(long, value_name = "URL")]
+        remote: Option<String>,
+    },
+    /// Print all bookmarks with their metadata, to back them up or for other tools
+    Export {
+        #[arg(long, value_enum, default_value = "plain")]
+        format: ExportFormat,
+    },
+    /// Manage separate sets of bookmarks
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCmd,
+    },
+    /// Copy the bookmarks of the other backend into this one
+    Migrate {
+        #[arg(long, value_enum, default_value = "text")]
+        from: Backend,
+        #[arg(long, value_enum, default_value = "sqlite")]
+        to: Backend,
+    },
+    /// Print completions of the pathmarks command itself for a shell
+    Completions { shell: clap_complete::Shell },
+    /// Print a roff man page, for packagers
+    #[command(hide = true)]
+    Man,
+    Init {
+        shell: Shell,
+        command: Option<String>,
+        #[command(flatten)]
+        options: InitOptions,
+        /// Add this init to the shell's rc file instead of printing it
+        #[arg(long, conflicts_with = "uninstall")]
+        install: bool,
+        /// Remove a previously installed init from the shell's rc file
+        #[arg(long)]
+        uninstall: bool,
+    },
+    /// Print the nushell helpers as a module, to load with `overlay use`
+    NuModule {
+        command: Option<String>,
+        #[command(flatten)]
+        options: InitOptions,
+    },
+    /// Print the config showing `pathmarks status` in a prompt, to add to
+    /// the prompt's config file
+    Prompt { prompt: Prompt },
+}
+
+#[derive(Subcommand)]
+enum TagCmd {
+    Add {
+        path: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    Remove {
+        path: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// List the tags of a bookmark, or all tags in use
+    List { path: Option<String> },
+}
+
+#[derive(Subcommand)]
+enum SlotCmd {
+    /// Put a bookmark in a slot, taking the slot from any other bookmark
+    Set {
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=9))]
+        slot: u8,
+        path: Option<String>,
+    },
+    /// Empty a slot
+    Clear {
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=9))]
+        slot: u8,
+    },
+    /// List the bookmarks in slots
+    List,
+}
+
+#[derive(Subcommand)]
+enum ProfileCmd {
+    /// List the profiles that exist
+    List,
+}
+
+pub fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let log_file = data_dir().ok().map(|dir| dir.join("pathmarks.log"));
+    logger::init(
+        cli.quiet,
+        cli.verbose,
+        env::var(LOG_ENV).ok().as_deref(),
+        log_file.as_deref(),
+    );
+
+    match run(cli) {
+        Ok(res) => {
+            if let Some(res) = res {
+                println!("{res}")
+            }
+            ExitCode::SUCCESS
+        }
+        // Closing the picker is not worth a message.
+        Err(AppError::Cancelled) => {
+            info!("The picker was closed without picking");
+            ExitCode::from(AppError::Cancelled.exit_code())
+        }
+        Err(err) => {
+            error!("{err}");
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+fn run(cli: Cli) -> AppResult<Option<String>> {
+    let bookmark_path = bookmarks_file(cli.store.clone(), cli.profile.as_deref())?;
+    let config = Config::load()?;
+    app(cli, bookmark_path, config)
+}
+
+fn app(cli: Cli, bookmarks_file: PathBuf, config: Config) -> AppResult<Option<String>> {
+    if let Some(cwd) = &cli.cwd {
+        env::set_current_dir(cwd)?;
+    }
+
+    let store = open_store(
+        config.backend,
+        bookmarks_file.clone(),
+        config.encryption.clone(),
+    )?;
+    theme::init(config.theme.clone(), cli.color);
+
+    let store = daemon::connect(store);
+    let store = store.as_ref();
+    let journal = Journal::new(store.file(), config.encryption.clone());
+    let matching = &cli.matching.clone().or(config.matching.clone());
+    let quiet = cli.quiet;
+    let path_map = &PathMap {
+        mappings: &config.path_map,
+        wsl: config.wsl,
+    };
+
+    match cli.command {
+        Cmd::Save {
+            name,
+            project,
+            root,
+            file,
+            tags,
+            note,
+        } => {
+            for value in name.iter().chain(&note) {
+                validate_value(value)?;
+            }
+            for tag in &tags {
+                validate_tag(tag)?;
+            }
+            let cwd = bookmark_path(&logical_current_dir()?, config.keep_symlinks)?;
+            let dir = if root {
+                project_root(&cwd).ok_or(AppError::NoProjectRoot)?
+            } else if let Some(file) = file {
+                let path = bookmark_path(&cwd.join(expand_home(&file)), config.keep_symlinks)
+                    .ok()
+                    .filter(|path| path.is_file());
+                path.ok_or_else(|| AppError::NotFound(file.to_string_lossy().into_owned()))?
+            } else {
+                cwd
+            };
+
+            let saved = Bookmark {
+                name,
+                tags,
+                note,
+                ..Default::default()
+            };
+
+            if project {
+                save_project_bookmark(&dir, saved)?;
+                config.hooks.saved(&dir);
+                return Ok(None);
+            }
+
+            let lock = store.lock()?;
+            let mut bookmarks = store.read()?;
+            let saved = Bookmark {
+                path: to_store(path_map, dir.clone()),
+                created_at: Some(now()),
+                ..saved
+            };
+            insert_bookmark(&mut bookmarks, saved, &dir);
+            store.write(&bookmarks)?;
+            // Hooks may run pathmarks themselves.
+            drop(lock);
+            config.hooks.saved(&dir);
+            Ok(None)
+        }
+        Cmd::Add { paths } => {
+            let cwd = logical_current_dir()?;
+            let _lock = store.lock()?;
+            let mut bookmarks = store.read()?;
+
+            for path in paths {
+                if is_remote(&path) {
+                    let remote = path.to_string_lossy();
+                    remote::parse(&remote)
+                        .ok_or_else(|| AppError::InvalidRemote(remote.to_string()))?;
+                    let added = Bookmark {
+                        created_at: Some(now()),
+                        ..Bookmark::new(path.clone())
+                    };
+                    insert_bookmark(&mut bookmarks, added, &path);
+                    continue;
+                }
+                let local = bookmark_path(&cwd.join(expand_home(&path)), config.keep_symlinks)
+                    .map_err(|_| AppError::NotFound(path.to_string_lossy().into_owned()))?;
+                let added = Bookmark {
+                    created_at: Some(now()),
+                    ..Bookmark::new(to_store(path_map, local.clone()))
+                };
+                insert_bookmark(&mut bookmarks, added, &local);
+            }
+
+            store.write(&bookmarks)?;
+            Ok(None)
+        }
+        Cmd::Remove {
+            path,
+            force_nearest,
+        } => {
+            // Picking happens before locking, so other shells are not
+            // blocked while the picker is open.
+            let mut targets: Vec<PathBuf> = match path {
+                Some(path) if !is_absolute(&path) => return Err(AppError::InvalidPath),
+                Some(path) => vec![stored_form(path_map, PathBuf::from(path))],
+                None => pick_many(&store.read()?)?
+                    .into_iter()
+                    .map(|b| stored_form(path_map, b.path.clone()))
+                    .collect(),
+            };
+
+            let lock = store.lock()?;
+            let mut bookmarks = store.read()?;
+
+            for target in &mut targets {
+                if !bookmarks
+                    .iter()
+                    .any(|b| is_stored(path_map, &b.path, target))
+                {
+                    let query = target.to_string_lossy().into_owned();
+                    let nearest: Vec<_> =
+                        suggestions(&query, bookmarks.iter().filter_map(|b| b.path.to_str()))
+                            .into_iter()
+                            .map(String::from)
+                            .collect();
+
+                    match nearest.first() {
+                        Some(closest) if force_nearest => {
+                            *target = stored_form(path_map, PathBuf::from(closest))
+                        }
+                        Some(_) => return Err(AppError::NotFoundSuggestions(query, nearest)),
+                        None => return Err(AppError::NotFound(query)),
+                    }
+                }
+            }
+
+            bookmarks.retain(|b| !targets.iter().any(|t| is_stored(path_map, &b.path, t)));
+            journal.write(store, "remove", now(), &bookmarks)?;
+            drop(lock);
+            for target in targets {
+                config
+                    .hooks
+                    .removed(&to_local(path_map, expand_stored(target)));
+            }
+            Ok(None)
+        }
+
+        Cmd::Query {
+            terms,
+            list,
+            limit,
+            score,
+            porcelain: version,
+            exclude,
+            interactive,
+        } => {
+            let mut bookmarks = visible_bookmarks(store, &env::current_dir()?, path_map)?;
+            // Shown bookmarks are in their local form, so compare both in the
+            // form they are stored in.
+            let exclude: Vec<_> = exclude
+                .into_iter()
+                .map(|path| to_store(path_map, expand_stored(path)))
+                .collect();
+            bookmarks.retain(|b| {
+                let stored = to_store(path_map, b.path.clone());
+                !exclude.iter().any(|path| same_path(&stored, path))
+            });
+            let now = now();
+            let query = terms.join(" ");
+
+            let ranked: Vec<(&Bookmark, f64)> = if terms.is_empty() {
+                let mut ranked: Vec<_> = bookmarks.iter().map(|b| (b, frecency(b, now))).collect();
+                ranked.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+                ranked
+            } else {
+                let find = |path: &str| bookmarks.iter().find(|b| b.path.to_str() == Some(path));
+                let weight = |path: &str| find(path).map_or(1.0, |b| match_weight(b, now));
+                let candidates = bookmarks.iter().flat_map(|b| b.path.to_str());
+                matching
+                    .ranked(&query, candidates, weight)
+                    .into_iter()
+                    .filter_map(|(path, score)| Some((find(path)?, score)))
+                    .collect()
+            };
+            if ranked.is_empty() {
+                return Err(AppError::NotFound(query));
+            }
+
+            let limit = limit.unwrap_or(if list || interactive { usize::MAX } else { 1 });
+            let ranked = ranked.into_iter().take(limit);
+
+            if interactive {
+                let matches: Vec<_> = ranked.map(|(b, _)| b.clone()).collect();
+                return Ok(Some(
+                    pick_one(&matches)?.path.to_string_lossy().into_owned(),
+                ));
+            }
+
+            if let Some(version) = version {
+                let matches: Vec<_> = ranked.map(|(b, _)| b.clone()).collect();
+                return Ok(Some(porcelain(version, &matches, now).join("\n")));
+            }
+
+            let lines: Vec<_> = ranked
+                .map(|(b, score)| (b.path.to_string_lossy(), score))
+                .map(|(path, s)| {
+                    if score {
+                        format!("{s:.0}\t{path}")
+                    } else {
+                        path.to_string()
+                    }
+                })
+                .collect();
+            Ok(Some(lines.join("\n")))
+        }
+        Cmd::Guess {
+            paths,
+            no_interactive,
+        } => {
+            if paths.is_empty() {
+                return Ok(None);
+            }
+
+            let current_dir = env::current_dir()?;
+            let bookmarks = visible_bookmarks(store, &current_dir, path_map)?;
+
+            let mut path = guess(
+                &paths,
+                &current_dir,
+                &bookmarks,
+                config.search_depth,
+                matching,
+            );
+            // Only asks when the bookmarks matched, not a name or a directory.
+            if !no_interactive
+                && io::stdin().is_terminal()
+                && let [query] = paths.as_slice()
+                && !bookmarks.iter().any(|b| b.name.as_deref() == Some(query))
+            {
+                let close = close_matches(query, &bookmarks, matching);
+                if close.iter().any(|b| b.path == path) {
+                    path = pick_one(&close)?.path.clone();
+                }
+            }
+            if !path.exists() && !is_remote(&path) {
+                return Err(AppError::NotFound(paths.join(" ")));
+            }
+            mark_used(store, &path, path_map)?;
+            config.hooks.jumped(&path);
+            Ok(Some(path.to_string_lossy().into_owned()))
+        }
+        Cmd::Complete { partial } => {
+            let current_dir = env::current_dir()?;
+            let bookmarks = visible_bookmarks(store, &current_dir, path_map)?;
+            let completions = complete(&partial, &bookmarks, matching);
+            Ok((!completions.is_empty()).then(|| completions.join("\n")))
+        }
+        Cmd::Open {
+            query,
+            with,
+            remember,
+        } => {
+            if let Some(with) = &with {
+                validate_value(with)?;
+            }
+            let current_dir = env::current_dir()?;
+            let bookmarks = visible_bookmarks(store, &current_dir, path_map)?;
+
+            let target = if query.is_empty() {
+                pick_one(&bookmarks)?.path.clone()
+            } else {
+                let path = guess(
+                    &query,
+                    &current_dir,
+                    &bookmarks,
+                    config.search_depth,
+                    matching,
+                );
+                if !path.exists() {
+                    return Err(AppError::NotFound(query.join(" ")));
+                }
+                path
+            };
+
+            let default = bookmarks
+                .iter()
+                .find(|b| b.path == target)
+                .and_then(|b| b.opener.as_deref());
+            open(&target, with.as_deref().or(default), &config.openers)?;
+            mark_used(store, &target, path_map)?;
+
+            if remember {
+                let stored = to_store(path_map, target);
+                let _lock = store.lock()?;
+                let mut bookmarks = store.read()?;
+                let bookmark = bookmarks
+                    .iter_mut()
+                    .find(|b| is_stored(path_map, &b.path, &stored))
+                    .ok_or_else(|| AppError::NotFound(stored.to_string_lossy().into_owned()))?;
+                bookmark.opener = with;
+                store.write(&bookmarks)?;
+            }
+
+            Ok(None)
+        }
+        Cmd::Exec { query, command } => {
+            let current_dir = env::current_dir()?;
+            let bookmarks = visible_bookmarks(store, &current_dir, path_map)?;
+            let dir = guess(
+                &query,
+                &current_dir,
+                &bookmarks,
+                config.search_depth,
+                matching,
+            );
+            if !dir.is_dir() {
+                return Err(AppError::NotFound(query.join(" ")));
+            }
+            mark_used(store, &dir, path_map)?;
+
+            let (program, args) = command.split_first().ok_or(AppError::EmptyCommand)?;
+            debug!("Running {program} in {}", dir.display());
+            let status = Command::new(program)
+                .args(args)
+                .current_dir(&dir)
+                .status()?;
+            if !status.success() {
+                return Err(AppError::Exec(program.clone(), status));
+            }
+            Ok(None)
+        }
+        Cmd::Tmux { query, session } => {
+            let current_dir = env::current_dir()?;
+            let bookmarks = visible_bookmarks(store, &current_dir, path_map)?;
+            let dir = if query.is_empty() {
+                pick_one(&bookmarks)?.path.clone()
+            } else {
+                guess(
+                    &query,
+                    &current_dir,
+                    &bookmarks,
+                    config.search_depth,
+                    matching,
+                )
+            };
+            if !dir.is_dir() {
+                return Err(AppError::NotFound(query.join(" ")));
+            }
+            mark_used(store, &dir, path_map)?;
+
+            let bookmark = bookmarks.iter().find(|b| b.path == dir);
+            let name = tmux::name(bookmark.unwrap_or(&Bookmark::new(dir.clone())));
+            tmux::switch(&dir, &name, session)?;
+            Ok(None)
+        }
+        Cmd::Status { format } => {
+            let physical = env::current_dir()?;
+            let logical = logical_current_dir()?;
+            let bookmark = store.read()?.into_iter().find(|b| {
+                let local = to_local(path_map, expand_stored(b.path.clone()));
+                same_path(&local, &logical) || same_path(&local, &physical)
+            });
+            Ok(bookmark.map(|b| status::status(&format, &b)))
+        }
+        Cmd::Prune {
+            unused_for,
+            dry_run,
+            interactive,
+            grandparent,
+            timeout,
+        } => {
+            let cutoff = unused_for.map(|age| now().saturating_sub(age));
+            let timeout = timeout.map(Duration::from_secs);
+            let bookmarks = store.read()?;
+            let total = bookmarks.len();
+            let progress = Progress::new(total, !quiet);
+            let interrupt = Interrupt::register()?;
+
+            let (mut pruned, used): (Vec<_>, Vec<_>) = bookmarks
+                .into_iter()
+                .partition(|b| cutoff.zip(b.last_used).is_some_and(|(c, t)| t < c));
+
+            // Stored paths may start with `~` or be in the form of the host.
+            let paths: Vec<_> = used
+                .iter()
+                .map(|b| to_local(path_map, expand_stored(b.path.clone())))
+                .collect();
+            let mut missing = vec![false; paths.len()];
+            let mut timed_out = 0;
+            let mut checked = pruned.len();
+            check_all(&paths, timeout, |i, exists| {
+                checked += 1;
+                progress.update(checked, &paths[i]);
+                match exists {
+                    Some(true) => {}
+                    Some(false) => missing[i] = true,
+                    None => timed_out += 1,
+                }
+                // Once interrupted, the remaining bookmarks are kept unchecked.
+                !interrupt.raised()
+            });
+            progress.finish();
+
+            let mut moved = Vec::new();
+            for ((bookmark, local), _) in used
+                .into_iter()
+                .zip(&paths)
+                .zip(missing)
+                .filter(|(_, missing)| *missing)
+            {
+                match find_moved(local, grandparent) {
+                    Some(new) => moved.push((bookmark, to_store(path_map, new))),
+                    None => pruned.push(bookmark),
+                }
+            }
+            if timed_out > 0 {
+                warn!("Kept {timed_out} bookmarks that took too long to check");
+            }
+            if checked < total {
+                warn!("Interrupted after checking {checked} of {total} bookmarks");
+            }
+
+            if dry_run {
+                let lines: Vec<_> =
+                    pruned
+                        .iter()
+                        .map(|b| b.path.display().to_string())
+                        .chain(moved.iter().map(|(old, new)| {
+                            format!("{} -> {}", old.path.display(), new.display())
+                        }))
+                        .collect();
+                return Ok(Some(lines.join("\n")));
+            }
+            let moved = confirm_moves(moved, &mut pruned)?;
+            let pruned: Vec<_> = if interactive && !pruned.is_empty() {
+                pick_many(&pruned)?
+                    .into_iter()
+                    .map(|b| stored_form(path_map, b.path.clone()))
+                    .collect()
+            } else {
+                pruned
+                    .into_iter()
+                    .map(|b| stored_form(path_map, b.path))
+                    .collect()
+            };
+
+            // Checking can take a while, so the store is only locked and read
+            // again for removing what was found.
+            let _lock = store.lock()?;
+            let bookmarks = store.read()?;
+            let mut kept = bookmarks.clone();
+            kept.retain(|b| !pruned.iter().any(|p| is_stored(path_map, &b.path, p)));
+            for (old, new) in moved {
+                let old = stored_form(path_map, old);
+                if kept.iter().any(|b| is_stored(path_map, &b.path, &new)) {
+                    kept.retain(|b| !is_stored(path_map, &b.path, &old));
+                } else if let Some(bookmark) =
+                    kept.iter_mut().find(|b| is_stored(path_map, &b.path, &old))
+                {
+                    bookmark.path = new;
+                }
+            }
+            // A prune that changes nothing leaves nothing to undo.
+            if kept != bookmarks {
+                journal.write(store, "prune", now(), &kept)?;
+            }
+            Ok(None)
+        }
+        Cmd::List {
+            porcelain: None,
+            format: Format::Json,
+            regex,
+            tag,
+            sort: order,
+            ..
+        } => {
+            let bookmarks = visible_bookmarks(store, &env::current_dir()?, path_map)?;
+            let bookmarks = matching_regex(bookmarks, regex.as_ref());
+            let mut bookmarks = with_tag(bookmarks, tag.as_deref());
+            sort(&mut bookmarks, order, now());
+            Ok(Some(json(&bookmarks, now())))
+        }
+        Cmd::List {
+            porcelain: None,
+            pretty,
+            numbered,
+            regex,
+            tag,
+            sort: order,
+            entries,
+            hidden,
+            print0,
+            absolute,
+            relative,
+            ..
+        } => {
+            let entries = entries.or(config.entries.unwrap_or(Entries::Bookmarks));
+            let hidden = hidden || config.hidden;
+            let current_dir = env::current_dir()?;
+            let bookmarks = if entries.bookmarks() {
+                visible_bookmarks(store, &current_dir, path_map)?
+            } else {
+                Vec::new()
+            };
+            let bookmarks = matching_regex(bookmarks, regex.as_ref());
+            let mut bookmarks = with_tag(bookmarks, tag.as_deref());
+            sort(&mut bookmarks, order, now());
+
+            // Numbers are the ones `goto` uses. Project bookmarks are not
+            // saved and have none.
+            let saved = numbered_bookmarks(store, path_map)?;
+            let numbers: Vec<_> = bookmarks
+                .iter()
+                .map(|b| saved.iter().position(|s| s.path == b.path).map(|i| i + 1))
+                .collect();
+            let width = saved.len().to_string().len();
+
+            // Directories in the current one come first, like in the picker.
+            let dirs = if entries.dirs() {
+                list_child_dirs(&current_dir, hidden)?
+            } else {
+                Vec::new()
+            };
+            let numbers = iter::repeat_n(None, dirs.len()).chain(numbers);
+            let theme = theme::theme(&io::stdout());
+            let after_dirs = !dirs.is_empty();
+            let out = dirs
+                .into_iter()
+                .map(|dir| (Bookmark::new(dir), true))
+                .chain(bookmarks.into_iter().map(|b| (b, false)));
+
+            let out: Vec<_> = out
+                .zip(numbers)
+                .filter_map(|((b, dir), number)| {
+                    let style = theme.entry(&b.path, b.pinned, dir, after_dirs);
+                    let path = if absolute {
+                        b.path
+                    } else if relative {
+                        relative_to(&current_dir, &b.path)
+                    } else {
+                        // The current directory itself is left out.
+                        match relative_if_descendant(&current_dir, &b.path) {
+                            Some(rel) if rel == Path::new(".") => return None,
+                            Some(rel) => rel,
+                            None => b.path,
+                        }
+                    };
+                    Some((Bookmark { path, ..b }, number, style))
+                })
+                .map(|(b, number, style)| {
+                    let path = style.paint(&b.path.to_string_lossy());
+                    let line = if pretty {
+                        let pin = if b.pinned { PIN } else { "  " };
+                        let blank = if b.path.is_file() { FILE } else { "  " };
+                        let icon = b.icon.as_deref().unwrap_or(blank);
+                        match b.name {
+                            Some(name) => format!("{pin} {icon} {path} ({name})"),
+                            None => format!("{pin} {icon} {path}"),
+                        }
+                    } else {
+                        path
+                    };
+
+                    match number {
+                        Some(n) if numbered => format!("{n:>width$} {line}"),
+                        None if numbered => format!("{:width$} {line}", ""),
+                        _ => line,
+                    }
+                })
+                .collect();
+
+            if print0 {
+                let mut stdout = io::stdout().lock();
+                for line in out {
+                    stdout.write_all(line.as_bytes())?;
+                    stdout.write_all(b"\0")?;
+                }
+                return Ok(None);
+            }
+            Ok(Some(out.join("\n")))
+        }
+        Cmd::List {
+            porcelain: Some(version),
+            regex,
+            tag,
+            sort: order,
+            print0,
+            ..
+        } => {
+            let bookmarks = visible_bookmarks(store, &env::current_dir()?, path_map)?;
+            let bookmarks = matching_regex(bookmarks, regex.as_ref());
+            let mut bookmarks = with_tag(bookmarks, tag.as_deref());
+            let now = now();
+            sort(&mut bookmarks, order, now);
+
+            let records = porcelain(version, &bookmarks, now);
+            if print0 {
+                let mut stdout = io::stdout().lock();
+                for record in records {
+                    stdout.write_all(record.as_bytes())?;
+                    stdout.write_all(b"\0")?;
+                }
+                return Ok(None);
+            }
+            Ok(Some(records.join("\n")))
+        }
+        Cmd::Pick {
+            tag,
+            multi: true,
+            entries,
+            hidden,
+            query,
+            select_1,
+            ..
+        } => {
+            let entries = entries.or(config.entries.unwrap_or(Entries::Bookmarks));
+            let hidden = hidden || config.hidden;
+            let current_dir = env::current_dir()?;
+            let bookmarks = if entries.bookmarks() {
+                visible_bookmarks(store, &current_dir, path_map)?
+            } else {
+                Vec::new()
+            };
+            let mut bookmarks = with_tag(bookmarks, tag.as_deref());
+            sort_by_frecency(&mut bookmarks, now());
+            if entries.dirs() {
+                let dirs = list_child_dirs(&current_dir, hidden)?;
+                bookmarks.splice(0..0, dirs.into_iter().map(Bookmark::new));
+            }
+
+            let query = Query {
+                text: &query,
+                select_1,
+            };
+            let picked: Vec<_> = pick_many_matching(&bookmarks, &query)?
+                .into_iter()
+                .map(|b| b.path.to_string_lossy())
+                .collect();
+            Ok(Some(picked.join("\n")))
+        }
+        Cmd::Pick {
+            tag,
+            multi: false,
+            recent,
+            entries,
+            hidden,
+            depth,
+            query,
+            select_1,
+        } => {
+            let query = Query {
+                text: &query,
+                select_1,
+            };
+            let entries = entries.or(config.entries.unwrap_or(Entries::Merged));
+            let hidden = hidden || config.hidden;
+            let depth = depth.or(config.merge_depth).unwrap_or(1);
+            let current_dir = env::current_dir()?;
+            let mut relative_sub_directories = Vec::new();
+            if entries.dirs() {
+                let sub_directories = list_child_dirs(&current_dir, hidden)?;
+                relative_sub_directories = map_relative_paths(&current_dir, sub_directories);
+                relative_sub_directories.push(PathBuf::from(".."));
+            }
+
+            // Like the recent directories, picks are not kept in plain text
+            // next to an encrypted store.
+            let last_pick_file = config
+                .encryption
+                .is_none()
+                .then(|| last_pick_file(store.file()));
+            let last_pick = last_pick_file
+                .as_deref()
+                .and_then(|file| read_last_pick(file, &current_dir));
+
+            // Deleting a bookmark from the picker opens it again without it.
+            loop {
+                let bookmarks = if entries.bookmarks() {
+                    visible_bookmarks(store, &current_dir, path_map)?
+                } else {
+                    Vec::new()
+                };
+                let recent = if recent && entries.bookmarks() {
+                    unbookmarked_recent(store, &bookmarks, &current_dir, path_map)
+                } else {
+                    Vec::new()
+                };
+                let mut bookmarks = with_tag(bookmarks, tag.as_deref());
+                let now = now();
+                sort_by_frecency(&mut bookmarks, now);
+                bookmarks.extend(recent);
+                let relative_bookmarks = map_relative_bookmarks(&current_dir, bookmarks);
+
+                let deeper = if entries.dirs() { depth } else { 0 };
+                let deeper = deeper_dirs(current_dir.clone(), deeper, hidden)
+                    .map(|dir| relative_if_descendant(&current_dir, &dir).unwrap_or(dir));
+
+                match pick_one_last_dim(
+                    &relative_sub_directories,
+                    &relative_bookmarks,
+                    last_pick.as_deref(),
+                    deeper,
+                    &query,
+                )? {
+                    Picked::Select(bookmark) => {
+                        if let Some(file) = &last_pick_file {
+                            write_last_pick(file, &current_dir, &bookmark)?;
+                        }
+                        let path = current_dir.join(&bookmark);
+                        mark_used(store, &path, path_map)?;
+                        config.hooks.jumped(&path);
+                        return Ok(bookmark.to_str().map(|x| x.into()));
+                    }
+                    Picked::Delete(bookmark) => {
+                        let path = current_dir.join(bookmark);
+                        let stored = to_store(path_map, path.clone());
+                        let lock = store.lock()?;
+                        let mut bookmarks = store.read()?;
+                        let count = bookmarks.len();
+                        bookmarks.retain(|b| !is_stored(path_map, &b.path, &stored));
+                        if bookmarks.len() < count {
+                            journal.write(store, "remove", now, &bookmarks)?;
+                            drop(lock);
+                            config.hooks.removed(&path);
+                        }
+                    }
+                }
+            }
+        }
+        Cmd::Visit { path } => {
+            let path = path.canonicalize().map(simplify).unwrap_or(path);
+            let stored = to_store(path_map, path.clone());
+            let recent = recent_file(store.file());
+            let lock = store.lock()?;
+            // The recent directories are plain text, so they would give away
+            // what encryption hides.
+            let times = match config.encryption {
+                None => record_visit(&recent, &stored, now())?,
+                Some(_) => Vec::new(),
+            };
+            let mut bookmarks = store.read()?;
+
+            if let Some(index) = bookmarks
+                .iter()
+                .position(|b| is_stored(path_map, &b.path, &stored))
+            {
+                visit(&mut bookmarks, index, now());
+                store.write(&bookmarks)?;
+            } else if let Some(promote) = &config.promote
+                && promote.due(&times, now())
+                && path.is_dir()
+            {
+                info!("Bookmarked {}, which is visited often", path.display());
+                let saved = Bookmark {
+                    visits: Some(1),
+                    last_used: Some(now()),
+                    ..Bookmark::new(stored.clone())
+                };
+                insert_bookmark(&mut bookmarks, saved, &path);
+                store.write(&bookmarks)?;
+                restart_count(&recent, &stored)?;
+                drop(lock);
+                config.hooks.saved(&path);
+            }
+            Ok(None)
+        }
+        Cmd::Stats { porcelain, format } => {
+            let bookmarks: Vec<_> = store
+                .read()?
+                .into_iter()
+                .map(|b| Bookmark {
+                    path: to_local(path_map, expand_stored(b.path)),
+                    ..b
+                })
+                .collect();
+            let size = fs::metadata(store.file()).map_or(0, |m| m.len());
+
+            let stats = stats(&bookmarks, size);
+            if porcelain.is_some() {
+                return Ok(Some(stats.porcelain().join("\n")));
+            }
+            match format {
+                Format::Plain => Ok(Some(stats.plain(now()))),
+                Format::Json => Ok(Some(stats.json())),
+            }
+        }
+        Cmd::Doctor { fix } => {
+            let _lock = if fix { Some(store.lock()?) } else { None };
+
+            // Only a plain text store has lines that can fail to parse.
+            let raw = (config.backend == Backend::Text && config.encryption.is_none())
+                .then(|| fs::read(store.file()))
+                .transpose()?;
+            let bookmarks = match &raw {
+                Some(content) => parse_bookmarks(&readable_content(content)),
+                None => store.read()?,
+            };
+
+            let mut problems = raw.as_deref().map(unreadable_lines).unwrap_or_default();
+            problems.extend(check_bookmarks(&bookmarks, config.keep_symlinks));
+            problems.extend(check_permissions(store.file()));
+            let files = [
+                store.file().to_path_buf(),
+                recent_file(store.file()),
+                last_pick_file(store.file()),
+                data_dir()?.join("pathmarks.log"),
+            ];
+            let limit = config.max_file_kb.unwrap_or(1024) * 1024;
+            problems.extend(check_sizes(&files, limit));
+            if problems.is_empty() {
+                return Ok(Some("No problems found".to_string()));
+            }
+
+            let mut lines: Vec<_> = problems.iter().map(ToString::to_string).collect();
+            if fix {
+                let backup = backup(store.file())?;
+                store.write(&repair(bookmarks, config.keep_symlinks))?;
+                fix_permissions(store.file())?;
+
+                let fixed = problems.iter().filter(|p| p.fixable()).count();
+                lines.push(format!(
+                    "Fixed {fixed} of {} problems, the old store is at {}",
+                    problems.len(),
+                    backup.display()
+                ));
+            }
+            Ok(Some(lines.join("\n")))
+        }
+        Cmd::Recent { limit } => {
+            let recent: Vec<_> = read_recent(&recent_file(store.file()))
+                .into_iter()
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|(dir, _)| to_local(path_map, dir).to_string_lossy().into_owned())
+                .collect();
+            Ok(Some(recent.join("\n")))
+        }
+        Cmd::Goto { index } => {
+            let bookmarks = numbered_bookmarks(store, path_map)?;
+            let slotted = bookmarks
+                .iter()
+                .find(|b| b.slot.is_some_and(|s| usize::from(s) == index));
+
+            match slotted.or_else(|| index.checked_sub(1).and_then(|i| bookmarks.get(i))) {
+                Some(bookmark) => {
+                    let path = bookmark.path.clone();
+                    mark_used(store, &path, path_map)?;
+                    Ok(Some(path.to_string_lossy().into_owned()))
+                }
+                None => Err(AppError::NotFound(format!("bookmark {index}"))),
+            }
+        }
+        Cmd::Icon { icon, path } => {
+            validate_value(&icon)?;
+            let target = target_bookmark(&store.read()?, path, path_map)?;
+            let _lock = store.lock()?;
+            let mut bookmarks = store.read()?;
+
+            let bookmark = bookmarks
+                .iter_mut()
+                .find(|b| is_stored(path_map, &b.path, &target))
+                .ok_or_else(|| AppError::NotFound(target.to_string_lossy().into_owned()))?;
+            bookmark.icon = (!icon.is_empty()).then_some(icon);
+            store.write(&bookmarks)?;
+            Ok(None)
+        }
+        Cmd::Move { path, to } => {
+            if !is_absolute(&path) {
+                return Err(AppError::InvalidPath);
+            }
+            let stored = stored_form(path_map, PathBuf::from(&path));
+
+            let _lock = store.lock()?;
+            let mut bookmarks = store.read()?;
+            let from = bookmarks
+                .iter()
+                .position(|b| is_stored(path_map, &b.path, &stored))
+                .ok_or(AppError::NotFound(path))?;
+            move_to(&mut bookmarks, from, to.saturating_sub(1));
+
+            store.write(&bookmarks)?;
+            Ok(None)
+        }
+        Cmd::Pin { path } => pin(store, path, true, path_map),
+        Cmd::Unpin { path } => pin(store, path, false, path_map),
+        Cmd::Import {
+            source,
+            file,
+            min_score,
+            dry_run,
+        } => {
+            let _lock = store.lock()?;
+            let mut bookmarks = store.read()?;
+
+            let imported: Vec<_> = imported(source, file.as_deref(), min_score)?
+                .into_iter()
+                .map(|b| Bookmark {
+                    path: to_store(path_map, b.path.clone()),
+                    ..b
+                })
+                .collect();
+
+            let mut new: Vec<&Path> = Vec::new();
+            for bookmark in &imported {
+                if !bookmarks
+                    .iter()
+                    .any(|b| same_location(&b.path, &bookmark.path))
+                    && !new.contains(&bookmark.path.as_path())
+                {
+                    new.push(&bookmark.path);
+                }
+            }
+
+            if dry_run {
+                let paths: Vec<_> = new.iter().map(|p| p.to_string_lossy()).collect();
+                return Ok(Some(paths.join("\n")));
+            }
+            let count = new.len();
+
+            for bookmark in imported {
+                let local = to_local(path_map, bookmark.path.clone());
+                let bookmark = Bookmark {
+                    created_at: bookmark.created_at.or(Some(now())),
+                    ..bookmark
+                };
+                insert_bookmark(&mut bookmarks, bookmark, &local);
+            }
+
+            store.write(&bookmarks)?;
+            Ok(Some(format!("Imported {count} bookmarks")))
+        }
+        Cmd::Merge { other } => {
+            let _lock = store.lock()?;
+            let mut bookmarks = store.read()?;
+            let other = parse_bookmarks(&fs::read_to_string(expand_home(&other))?);
+
+            let local = |path: &Path| to_local(path_map, expand_stored(path.to_path_buf()));
+            let summary = merge_into(&mut bookmarks, other, |ours, theirs| {
+                let theirs = local(theirs);
+                let canonical = theirs.canonicalize().map_or(theirs, simplify);
+                same_location(&local(ours), &canonical)
+            });
+
+            journal.write(store, "merge", now(), &bookmarks)?;
+            Ok(Some(summary.plain()))
+        }
+        Cmd::Undo { list: true } => {
+            let now = now();
+            let entries: Vec<_> = journal
+                .entries()
+                .into_iter()
+                .map(|e| {
+                    format!(
+                        "{} ago\t{}",
+                        format_age(now.saturating_sub(e.time)),
+                        e.operation
+                    )
+                })
+                .collect();
+            Ok(Some(entries.join("\n")))
+        }
+        Cmd::Undo { list: false } => {
+            let Some(entry) = journal.entries().into_iter().next() else {
+                return Ok(Some("Nothing to undo".to_string()));
+            };
+
+            let _lock = store.lock()?;
+            store.write(&journal.read(&entry)?)?;
+            let age = format_age(now().saturating_sub(entry.time));
+            let undone = format!("Undid {} from {age} ago", entry.operation);
+            journal.forget(entry)?;
+            Ok(Some(undone))
+        }
+        Cmd::Edit => {
+            edit(store, &journal)?;
+            Ok(None)
+        }
+        // `store` only goes through a daemon when one runs, and then `serve` fails.
+        Cmd::Daemon { watch } => {
+            let local = |path: &Path| to_local(path_map, expand_stored(path.to_path_buf()));
+            daemon::serve(store, watch, &local)?;
+            Ok(None)
+        }
+        Cmd::Ui => {
+            let local = |path: &Path| to_local(path_map, expand_stored(path.to_path_buf()));
+            let chosen = tui::ui(store, &journal, &local)?;
+            Ok(chosen.map(|path| path.to_string_lossy().into_owned()))
+        }
+        Cmd::Sync { remote } => {
+            let encryption = config.encryption.as_ref();
+            match (&config.sync, remote) {
+                (Some(commands), None) => {
+                    let _lock = store.lock()?;
+                    sync::sync_with_commands(store, commands, encryption)?
+                }
+                (Some(_), Some(_)) => return Err(AppError::SyncRemoteWithCommands),
+                (None, _) if config.backend != Backend::Text => {
+                    return Err(AppError::SyncUnsupported);
+                }
+                (None, remote) => {
+                    let data_dir = data_dir()?;
+                    if !store.file().starts_with(&data_dir) {
+                        let dir = data_dir.display().to_string();
+                        return Err(AppError::SyncOutsideDataDir(dir));
+                    }
+                    // Git commits and merges the stores of every profile.
+                    let _locks = profiles(&data_dir)
+                        .iter()
+                        .map(|profile| {
+                            let dir = profile_dir(&data_dir, profile)?;
+                            StoreLock::acquire(&dir.join("bookmarks.txt"))
+                        })
+                        .collect::<AppResult<Vec<_>>>()?;
+                    sync::sync(&data_dir, remote.as_deref(), encryption)?
+                }
+            }
+            Ok(None)
+        }
+        Cmd::Export { format } => {
+            let exported = export(&store.read()?, format);
+            Ok(Some(exported.trim_end_matches('\n').to_string()))
+        }
+        Cmd::Profile {
+            action: ProfileCmd::List,
+        } => Ok(Some(profiles(&data_dir()?).join("\n"))),
+        Cmd::Migrate { from, to } => migrate(bookmarks_file, &config, from, to),
+        Cmd::Tag { action } => tag(store, action, path_map),
+        Cmd::Slot { action } => slot(store, action, path_map),
+        Cmd::Completions { shell } => {
+            let mut out = Vec::new();
+            clap_complete::generate(shell, &mut Cli::command(), "pathmarks", &mut out);
+            Ok(Some(String::from_utf8_lossy(&out).trim_end().to_string()))
+        }
+        Cmd::Man => {
+            let mut out = Vec::new();
+            clap_mangen::Man::new(Cli::command()).render(&mut out)?;
+            Ok(Some(String::from_utf8_lossy(&out).trim_end().to_string()))
+        }
+        Cmd::Init {
+            shell,
+            command,
+            options,
+            install: true,
+            ..
+        } => {
+            let rc = rc_file(shell)?;
+            install(&rc, &init_line(shell, command.as_deref(), &options))?;
+            Ok(Some(format!("Added pathmarks to {}", rc.display())))
+        }
+        Cmd::Init {
+            shell,
+            uninstall: true,
+            ..
+        } => {
+            let rc = rc_file(shell)?;
+            uninstall(&rc)?;
+            Ok(Some(format!("Removed pathmarks from {}", rc.display())))
+        }
+        Cmd::Init {
+            shell,
+            command,
+            options,
+            ..
+        } => {
+            let mut out = init(shell, command, &options);
+            if options.hook {
+                out.push_str(&visit_hook(shell));
+            }
+            if options.abbr {
+                out.push_str(&abbreviations(shell, &store.read()?)?);
+            }
+            if options.cd {
+                out.push_str(&cd_fallback(shell)?);
+            }
+            if let Some(key) = &options.insert_key {
+                out.push_str(&insert_widget(shell, key)?);
+            }
+            if options.prompt {
+                out.push_str(&prompt(shell)?);
+            }
+            Ok(Some(out))
+        }
+        Cmd::NuModule { command, options } => Ok(Some(nu_module(command, &options)?)),
+        Cmd::Prompt { prompt } => Ok(Some(prompt_config(prompt))),
+    }
+}
+
+/// Personal bookmarks followed by the `.pathmarks` entries of the current
+/// repository.
+fn visible_bookmarks(
+    store: &dyn Store,
+    current_dir: &Path,
+    path_map: &PathMap,
+) -> AppResult<Vec<Bookmark>> {
+    let mut bookmarks = numbered_bookmarks(store, path_map)?;
+    for project in project_bookmarks(current_dir)? {
+        if !bookmarks.iter().any(|b| b.path == project.path) {
+            bookmarks.push(project);
+        }
+    }
+    bookmarks.sort_by_key(|b| !b.pinned);
+
+    Ok(bookmarks)
+}
+
+/// The personal bookmarks in local form and in the order `goto` numbers
+/// them: pinned ones first, then the saved order.
+fn numbered_bookmarks(store: &dyn Store, path_map: &PathMap) -> AppResult<Vec<Bookmark>> {
+    let dead = store.dead();
+    let mut bookmarks: Vec<_> = store
+        .read()?
+        .into_iter()
+        .filter(|b| !dead.contains(&b.path))
+        .map(|b| Bookmark {
+            path: to_local(path_map, expand_stored(b.path)),
+            ..b
+        })
+        .collect();
+    bookmarks.sort_by_key(|b| !b.pinned);
+
+    Ok(bookmarks)
+}
+
+/// Expands `~` and variables in a bookmark written by hand, e.g. through
+/// `pathmarks edit` or an import.
+fn expand_stored(path: PathBuf) -> PathBuf {
+    match path.to_str() {
+        Some(s) if s.contains(['~', '$']) => expand(s),
+        _ => path,
+    }
+}
+
+/// Recently visited directories that still exist and are not bookmarked,
+/// most recent first.
+fn unbookmarked_recent(
+    store: &dyn Store,
+    bookmarks: &[Bookmark],
+    current_dir: &Path,
+    path_map: &PathMap,
+) -> Vec<Bookmark> {
+    read_recent(&recent_file(store.file()))
+        .into_iter()
+        .map(|(dir, _)| to_local(path_map, dir))
+        .filter(|dir| dir != current_dir && dir.is_dir())
+        .filter(|dir| !bookmarks.iter().any(|b| b.path == *dir))
+        .map(Bookmark::new)
+        .collect()
+}
+
+fn matching_regex(bookmarks: Vec<Bookmark>, regex: Option<&Regex>) -> Vec<Bookmark> {
+    match regex {
+        Some(regex) => bookmarks
+            .into_iter()
+            .filter(|b| regex.is_match(&b.path.to_string_lossy()))
+            .collect(),
+        None => bookmarks,
+    }
+}
+
+fn with_tag(bookmarks: Vec<Bookmark>, tag: Option<&str>) -> Vec<Bookmark> {
+    match tag {
+        Some(tag) => bookmarks
+            .into_iter()
+            .filter(|b| b.tags.iter().any(|t| t == tag))
+            .collect(),
+        None => bookmarks,
+    }
+}
+
+fn tag(store: &dyn Store, action: TagCmd, path_map: &PathMap) -> AppResult<Option<String>> {
+    let _lock = store.lock()?;
+    let mut bookmarks = store.read()?;
+
+    let path = match &action {
+        TagCmd::List { path: None } => {
+            let mut tags: Vec<_> = bookmarks.into_iter().flat_map(|b| b.tags).collect();
+            tags.sort();
+            tags.dedup();
+            return Ok(Some(tags.join("\n")));
+        }
+        TagCmd::List { path: Some(path) }
+        | TagCmd::Add { path, .. }
+        | TagCmd::Remove { path, .. } => path.clone(),
+    };
+
+    let target = target_bookmark(&bookmarks, Some(path), path_map)?;
+    let bookmark = bookmarks
+        .iter_mut()
+        .find(|b| is_stored(path_map, &b.path, &target))
+        .ok_or_else(|| AppError::NotFound(target.to_string_lossy().into_owned()))?;
+
+    match action {
+        TagCmd::List { .. } => return Ok(Some(bookmark.tags.join("\n"))),
+        TagCmd::Add { tags, .. } => {
+            for tag in tags {
+                validate_tag(&tag)?;
+                if !bookmark.tags.contains(&tag) {
+                    bookmark.tags.push(tag);
+                }
+            }
+        }
+        TagCmd::Remove { tags, .. } => bookmark.tags.retain(|t| !tags.contains(t)),
+    }
+
+    store.write(&bookmarks)?;
+    Ok(None)
+}
+
+fn slot(store: &dyn Store, action: SlotCmd, path_map: &PathMap) -> AppResult<Option<String>> {
+    let (slot, target) = match action {
+        SlotCmd::List => {
+            let mut bookmarks: Vec<_> = store
+                .read()?
+                .into_iter()
+                .filter(|b| b.slot.is_some())
+                .collect();
+            bookmarks.sort_by_key(|b| b.slot);
+            let lines: Vec<_> = bookmarks
+                .into_iter()
+                .filter_map(|b| {
+                    let path = to_local(path_map, expand_stored(b.path));
+                    Some(format!("{}\t{}", b.slot?, path.to_string_lossy()))
+                })
+                .collect();
+            return Ok(Some(lines.join("\n")));
+        }
+        SlotCmd::Clear { slot } => (slot, None),
+        SlotCmd::Set { slot, path } => {
+            (slot, Some(target_bookmark(&store.read()?, path, path_map)?))
+        }
+    };
+
+    let _lock = store.lock()?;
+    let mut bookmarks = store.read()?;
+    if let Some(target) = &target
+        && !bookmarks
+            .iter()
+            .any(|b| is_stored(path_map, &b.path, target))
+    {
+        return Err(AppError::NotFound(target.to_string_lossy().into_owned()));
+    }
+
+    for bookmark in &mut bookmarks {
+        if let Some(target) = &target
+            && is_stored(path_map, &bookmark.path, target)
+        {
+            bookmark.slot = Some(slot);
+        } else if bookmark.slot == Some(slot) {
+            bookmark.slot = None;
+        }
+    }
+
+    store.write(&bookmarks)?;
+    Ok(None)
+}
+
+fn pin(
+    store: &dyn Store,
+    path: Option<String>,
+    pinned: bool,
+    path_map: &PathMap,
+) -> AppResult<Option<String>> {
+    let target = target_bookmark(&store.read()?, path, path_map)?;
+
+    let _lock = store.lock()?;
+    let mut bookmarks = store.read()?;
+    let bookmark = bookmarks
+        .iter_mut()
+        .find(|b| is_stored(path_map, &b.path, &target))
+        .ok_or_else(|| AppError::NotFound(target.to_string_lossy().into_owned()))?;
+    bookmark.pinned = pinned;
+
+    store.write(&bookmarks)?;
+    Ok(None)
+}
+
+/// Copies all bookmarks from one backend into another. The config is left
+/// alone, so switching `backend` over is up to the user.
+fn migrate(
+    bookmarks_file: PathBuf,
+    config: &Config,
+    from: Backend,
+    to: Backend,
+) -> AppResult<Option<String>> {
+    let source = open_store(from, bookmarks_file.clone(), config.encryption.clone())?;
+    let target = open_store(to, bookmarks_file, config.encryption.clone())?;
+
+    let bookmarks = source.read()?;
+    let _lock = target.lock()?;
+    target.write(&bookmarks)?;
+
+    Ok(Some(format!(
+        "Copied {} bookmarks to {}",
+        bookmarks.len(),
+        target.file().display()
+    )))
+}
+
+/// Adds `saved` to the bookmarks, merging it with an existing bookmark of the
+/// same location and keeping that one's position. `local` is the canonical
+/// path on this machine, used to name git repositories after their origin.
+fn insert_bookmark(bookmarks: &mut Vec<Bookmark>, mut saved: Bookmark, local: &Path) {
+    let existing = bookmarks
+        .iter()
+        .position(|b| same_location(&b.path, &saved.path));
+
+    bookmarks.retain(|b| {
+        let duplicate = same_location(&b.path, &saved.path);
+        if duplicate {
+            saved.merge(b.clone());
+        }
+        !duplicate
+    });
+    if saved.name.is_none() {
+        saved.name = origin_name(local).map(|name| unique_name(bookmarks, &name));
+    }
+    bookmarks.insert(existing.unwrap_or(bookmarks.len()), saved);
+}
+
+/// Asks on the terminal which moved bookmarks to update, returning the old
+/// and new paths of those. Declined bookmarks are added to `pruned`, like
+/// all of them when there is no terminal to ask on.
+fn confirm_moves(
+    moved: Vec<(Bookmark, PathBuf)>,
+    pruned: &mut Vec<Bookmark>,
+) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    if !io::stdin().is_terminal() {
+        pruned.extend(moved.into_iter().map(|(b, _)| b));
+        return Ok(Vec::new());
+    }
+
+    let mut confirmed = Vec::new();
+    for (bookmark, new) in moved {
+        eprint!(
+            "{} moved to {}? [Y/n] ",
+            bookmark.path.display(),
+            new.display()
+        );
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if matches!(answer.trim(), "" | "y" | "Y" | "yes") {
+            confirmed.push((bookmark.path, new));
+        } else {
+            pruned.push(bookmark);
+        }
+    }
+    Ok(confirmed)
+}
+
+/// Records that the bookmark at `path`, if there is one, was just jumped to.
+fn mark_used(store: &dyn Store, path: &Path, path_map: &PathMap) -> AppResult<()> {
+    let stored = to_store(path_map, path.to_path_buf());
+    let _lock = store.lock()?;
+    let mut bookmarks = store.read()?;
+
+    let Some(bookmark) = bookmarks
+        .iter_mut()
+        .find(|b| is_stored(path_map, &b.path, &stored))
+    else {
+        return Ok(());
+    };
+    bookmark.last_used = Some(now());
+
+    store.write(&bookmarks)
+}
+
+/// Whether a bookmark saved as `path`, maybe with `~` or `$VAR`, is the one
+/// `stored` names.
+fn is_stored(path_map: &PathMap, path: &Path, stored: &Path) -> bool {
+    same_path(&stored_form(path_map, path.to_path_buf()), stored)
+}
+
+/// `path` as pathmarks itself writes it to the store, with `~` and
+/// variables expanded, to compare bookmarks however they were saved.
+fn stored_form(path_map: &PathMap, path: PathBuf) -> PathBuf {
+    to_store(path_map, expand_stored(path))
+}
+
+/// Resolves the bookmark a command acts on, either from an absolute path
+/// argument or by asking the user to pick one.
+fn target_bookmark(
+    bookmarks: &[Bookmark],
+    path: Option<String>,
+    path_map: &PathMap,
+) -> AppResult<PathBuf> {
+    match path {
+        Some(path) if !is_absolute(&path) => Err(AppError::InvalidPath),
+        Some(path) => Ok(stored_form(path_map, PathBuf::from(path))),
+        None => Ok(stored_form(path_map, pick_one(bookmarks)?.path.clone())),
+    }
+}
+
+fn data_dir() -> AppResult<PathBuf> {
+    Ok(dirs::data_local_dir()
+        .ok_or(AppError::DataDirectoryNotFound)?
+        .join("pathmarks"))
+}
+
+fn bookmarks_file(store: Option<PathBuf>, profile: Option<&str>) -> AppResult<PathBuf> {
+    let file = match store {
+        Some(file) => expand_home(&file),
+        None => {
+            profile_dir(&data_dir()?, profile.unwrap_or(DEFAULT_PROFILE))?.join("bookmarks.txt")
+        }
+    };
+
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Never truncates, so a store another process just wrote to is kept.
+    match File::create_new(&file) {
+        Err(err) if err.kind() != io::ErrorKind::AlreadyExists => Err(err.into()),
+        _ => Ok(file),
+    }
+}
+
+/// The current directory the way the shell shows it, through symlinks.
+fn logical_current_dir() -> io::Result<PathBuf> {
+    let physical = env::current_dir()?;
+
+    match env::var_os("PWD").map(PathBuf::from) {
+        Some(pwd) if pwd.is_absolute() && same_location(&pwd, &physical) => Ok(pwd),
+        _ => Ok(physical),
+    }
+}
+
+fn same_location(bookmark: &Path, canonical: &Path) -> bool {
+    same_path(bookmark, canonical)
+        || bookmark
+            .canonicalize()
+            .is_ok_and(|p| same_path(&simplify(p), canonical))
+}
+
+/// Absolute paths and `ssh://` bookmarks, which name a bookmark as stored.
+fn is_absolute(p: &str) -> bool {
+    Path::new(p).is_absolute() || remote::parse(p).is_some()
+}
+
+/// Directories two to `max_depth` levels below `dir`, breadth first. Every
+/// directory is only read when the iterator gets to it.
+fn deeper_dirs(dir: PathBuf, max_depth: usize, hidden: bool) -> impl Iterator<Item = PathBuf> {
+    let mut queue = VecDeque::new();
+    if max_depth > 1 {
+        queue.push_back((dir, 0));
+    }
+
+    iter::from_fn(move || {
+        while let Some((dir, depth)) = queue.pop_front() {
+            if depth < max_depth {
+                let children = list_child_dirs(&dir, hidden).unwrap_or_default();
+                queue.extend(children.into_iter().map(|child| (child, depth + 1)));
+            }
+            if depth > 1 {
+                return Some(dir);
+            }
+        }
+        None
+    })
+}
+
+fn relative_if_descendant(base: &Path, child: &Path) -> Option<PathBuf> {
+    if !base.is_absolute() || !child.is_absolute() {
+        return None;
+    }
+
+    strip_base(child, base).map(|rel| {
+        if rel.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            rel
+        }
+    })
+}
+
+/// `path` relative to `base`, going up with `..` where `path` is not below
+/// it.
+fn relative_to(base: &Path, path: &Path) -> PathBuf {
+    if !base.is_absolute() || !path.is_absolute() {
+        return path.to_path_buf();
+    }
+    let base: Vec<_> = base.components().collect();
+    let path: Vec<_> = path.components().collect();
+    let common = base.iter().zip(&path).take_while(|(a, b)| a == b).count();
+
+    let relative: PathBuf = iter::repeat_n(Component::ParentDir, base.len() - common)
+        .chain(path[common..].iter().copied())
+        .collect();
+    if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative
+    }
+}
+
+fn map_relative_paths<I>(base: &Path, paths: I) -> Vec<PathBuf>
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    paths
+        .into_iter()
+        .map(|p| relative_if_descendant(base, &p).unwrap_or(p))
+        .filter(|p| p.to_str() != Some("."))
+        .collect()
+}
+
+fn map_relative_bookmarks(base: &Path, bookmarks: Vec<Bookmark>) -> Vec<Bookmark> {
+    bookmarks
+        .into_iter()
+        .map(|b| Bookmark {
+            path: relative_if_descendant(base, &b.path).unwrap_or(b.path),
+            ..b
+        })
+        .filter(|b| b.path.to_str() != Some("."))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_map::PathMapping;
+    use crate::recent::Promote;
+    use crate::store::{read_bookmarks, write_bookmarks};
+
+    #[test]
+    fn prune_checks_bookmarks_where_they_are_here() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.txt");
+        let missing = temp.path().join("missing");
+        let host = temp.path().join("host");
+        let container = temp.path().join("container");
+        fs::create_dir_all(&container).unwrap();
+        let stored = ["~".into(), missing.clone(), host.clone()];
+        write_bookmarks(&stored.map(Bookmark::new), &file).unwrap();
+
+        let config = Config {
+            path_map: vec![PathMapping { container, host }],
+            ..Default::default()
+        };
+        let cli = Cli::parse_from(["pathmarks", "prune", "--dry-run"]);
+
+        let pruned = app(cli, file, config).unwrap();
+        assert_eq!(pruned, Some(missing.display().to_string()));
+    }
+
+    #[test]
+    fn prune_without_changes_leaves_nothing_to_undo() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.txt");
+        write_bookmarks(&[Bookmark::new(temp.path().to_path_buf())], &file).unwrap();
+
+        let cli = Cli::parse_from(["pathmarks", "prune"]);
+        app(cli, file.clone(), Config::default()).unwrap();
+
+        let undo = file.with_file_name("undo");
+        assert!(fs::read_dir(undo).map_or(true, |mut entries| entries.next().is_none()));
+    }
+
+    #[test]
+    fn query_excludes_bookmarks_in_any_form() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.txt");
+        let kept = temp.path().join("kept");
+        let host = temp.path().join("host");
+        let container = temp.path().join("container");
+        fs::create_dir_all(&container).unwrap();
+        let stored = ["~".into(), host.clone(), kept.clone()];
+        write_bookmarks(&stored.map(Bookmark::new), &file).unwrap();
+
+        let config = Config {
+            path_map: vec![PathMapping {
+                container,
+                host: host.clone(),
+            }],
+            ..Default::default()
+        };
+        let cli = Cli::parse_from([
+            "pathmarks".as_ref(),
+            "query".as_ref(),
+            "--list".as_ref(),
+            "--exclude".as_ref(),
+            "~".as_ref(),
+            "--exclude".as_ref(),
+            host.as_os_str(),
+        ]);
+
+        let listed = app(cli, file, config).unwrap();
+        assert_eq!(listed, Some(kept.display().to_string()));
+    }
+
+    #[test]
+    fn starship_is_a_prompt_not_a_shell() {
+        assert!(Cli::try_parse_from(["pathmarks", "init", "starship", "--hook"]).is_err());
+
+        let temp = tempfile::tempdir().unwrap();
+        let cli = Cli::parse_from(["pathmarks", "prompt", "starship"]);
+        let out = app(cli, temp.path().join("bookmarks.txt"), Config::default()).unwrap();
+        assert!(out.unwrap().contains("command = \"pathmarks status\""));
+    }
+
+    #[test]
+    fn query_prints_porcelain_matches() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.txt");
+        let api = temp.path().join("api");
+        fs::create_dir(&api).unwrap();
+        let bookmarks = [
+            Bookmark::new(api.clone()),
+            Bookmark::new(temp.path().join("web")),
+        ];
+        write_bookmarks(&bookmarks, &file).unwrap();
+
+        let cli = Cli::parse_from(["pathmarks", "query", "--porcelain", "api"]);
+
+        let out = app(cli, file, Config::default()).unwrap();
+        assert_eq!(out, Some(format!("{}\t1", api.display())));
+    }
+
+    #[test]
+    fn visits_count_for_bookmarks_to_expand() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.txt");
+        write_bookmarks(&[Bookmark::new("~".into())], &file).unwrap();
+        let home = expand_home(Path::new("~"));
+
+        let cli = Cli::parse_from(["pathmarks".as_ref(), "visit".as_ref(), home.as_os_str()]);
+        app(cli, file.clone(), Config::default()).unwrap();
+
+        let bookmarks = read_bookmarks(&file).unwrap();
+        assert_eq!(bookmarks[0].visits, Some(1));
+    }
+
+    /// Runs `args` against a store holding `~/x` and `~/y`, returning the
+    /// bookmarks afterwards.
+    fn with_home_bookmarks(args: &[&str]) -> AppResult<Vec<Bookmark>> {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.txt");
+        let stored = ["~/x", "~/y"].map(|p| Bookmark::new(p.into()));
+        write_bookmarks(&stored, &file).unwrap();
+
+        let x = expand_home(Path::new("~/x"));
+        let args = args.iter().map(|arg| match *arg {
+            "X" => x.as_os_str(),
+            arg => arg.as_ref(),
+        });
+        let cli = Cli::parse_from(["pathmarks".as_ref()].into_iter().chain(args));
+        app(cli, file.clone(), Config::default())?;
+        Ok(read_bookmarks(&file).unwrap())
+    }
+
+    #[test]
+    fn remove_finds_bookmarks_to_expand() {
+        let bookmarks = with_home_bookmarks(&["remove", "X"]).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].path, PathBuf::from("~/y"));
+    }
+
+    #[test]
+    fn pin_finds_bookmarks_to_expand() {
+        let bookmarks = with_home_bookmarks(&["pin", "X"]).unwrap();
+        assert!(bookmarks[0].pinned);
+    }
+
+    #[test]
+    fn icon_finds_bookmarks_to_expand() {
+        let bookmarks = with_home_bookmarks(&["icon", "🚀", "X"]).unwrap();
+        assert_eq!(bookmarks[0].icon.as_deref(), Some("🚀"));
+    }
+
+    #[test]
+    fn tag_finds_bookmarks_to_expand() {
+        let bookmarks = with_home_bookmarks(&["tag", "add", "X", "work"]).unwrap();
+        assert_eq!(bookmarks[0].tags, ["work"]);
+    }
+
+    #[test]
+    fn slot_finds_bookmarks_to_expand() {
+        let bookmarks = with_home_bookmarks(&["slot", "set", "3", "X"]).unwrap();
+        assert_eq!(bookmarks[0].slot, Some(3));
+    }
+
+    #[test]
+    fn move_finds_bookmarks_to_expand() {
+        let bookmarks = with_home_bookmarks(&["move", "X", "--to", "2"]).unwrap();
+        assert_eq!(bookmarks[1].path, PathBuf::from("~/x"));
+    }
+
+    #[test]
+    fn open_remembers_for_bookmarks_to_expand() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.txt");
+        write_bookmarks(&[Bookmark::new("~".into())], &file).unwrap();
+        let home = expand_home(Path::new("~"));
+
+        let args = ["open", "--with", "true", "--remember"];
+        let args = args.iter().map(AsRef::as_ref).chain([home.as_os_str()]);
+        let cli = Cli::parse_from(["pathmarks".as_ref()].into_iter().chain(args));
+        app(cli, file.clone(), Config::default()).unwrap();
+
+        let bookmarks = read_bookmarks(&file).unwrap();
+        assert_eq!(bookmarks[0].opener.as_deref(), Some("true"));
+    }
+
+    #[test]
+    fn visits_promote_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.txt");
+        let dir = temp.path().canonicalize().unwrap().join("often");
+        fs::create_dir(&dir).unwrap();
+        write_bookmarks(&[], &file).unwrap();
+        let visit = || {
+            let config = Config {
+                promote: Some(Promote {
+                    visits: 2,
+                    within: 60,
+                }),
+                ..Default::default()
+            };
+            let cli = Cli::parse_from(["pathmarks".as_ref(), "visit".as_ref(), dir.as_os_str()]);
+            app(cli, file.clone(), config).unwrap();
+            read_bookmarks(&file).unwrap()
+        };
+
+        assert!(visit().is_empty());
+        assert_eq!(visit()[0].path, dir);
+    }
+
+    #[test]
+    fn goto_numbers_pinned_bookmarks_first() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.txt");
+        let bookmarks = [
+            Bookmark::new(PathBuf::from("/a")),
+            Bookmark {
+                pinned: true,
+                ..Bookmark::new(PathBuf::from("/b"))
+            },
+        ];
+        write_bookmarks(&bookmarks, &file).unwrap();
+        let goto = |n: &str| {
+            let cli = Cli::parse_from(["pathmarks", "goto", n]);
+            app(cli, file.clone(), Config::default()).unwrap()
+        };
+
+        assert_eq!(goto("1"), Some("/b".to_string()));
+        assert_eq!(goto("2"), Some("/a".to_string()));
+    }
+
+    #[test]
+    fn insert_merges_into_existing_position() {
+        let mut bookmarks = vec![
+            Bookmark {
+                icon: Some("🚀".to_string()),
+                ..Bookmark::new(PathBuf::from("/a"))
+            },
+            Bookmark::new(PathBuf::from("/b")),
+        ];
+        let added = Bookmark {
+            name: Some("a".to_string()),
+            ..Bookmark::new(PathBuf::from("/a"))
+        };
+
+        insert_bookmark(&mut bookmarks, added, Path::new("/a"));
+        insert_bookmark(
+            &mut bookmarks,
+            Bookmark::new(PathBuf::from("/c")),
+            Path::new("/c"),
+        );
+
+        let expected = Bookmark {
+            name: Some("a".to_string()),
+            icon: Some("🚀".to_string()),
+            ..Bookmark::new(PathBuf::from("/a"))
+        };
+        assert_eq!(bookmarks[0], expected);
+        assert_eq!(bookmarks.len(), 3);
+    }
+
+    #[test]
+    fn tag_filter() {
+        let tagged = Bookmark {
+            path: PathBuf::from("/work/api"),
+            tags: vec!["work".to_string()],
+            ..Default::default()
+        };
+        let bookmarks = vec![tagged.clone(), Bookmark::new(PathBuf::from("/home/me"))];
+
+        assert_eq!(with_tag(bookmarks.clone(), Some("work")), [tagged]);
+        assert_eq!(with_tag(bookmarks.clone(), None), bookmarks);
+    }
+
+    #[test]
+    fn regex_filter() {
+        let bookmarks = vec![
+            Bookmark::new(PathBuf::from("/mnt/data/photos")),
+            Bookmark::new(PathBuf::from("/mnt/backup")),
+            Bookmark::new(PathBuf::from("/home/me/mnt")),
+        ];
+        let regex = Regex::new("^/mnt/(backup|media)").unwrap();
+
+        let matched = matching_regex(bookmarks, Some(&regex));
+        assert_eq!(matched, [Bookmark::new(PathBuf::from("/mnt/backup"))]);
+    }
+
+    #[test]
+    fn write_bookmarks_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let file = dir.path().join("bookmarks.txt");
+
+        let bookmarks = vec![
+            Bookmark::new(PathBuf::from("/tmp/a")),
+            Bookmark {
+                path: PathBuf::from("/tmp/b"),
+                name: Some("b".to_string()),
+                icon: Some("🚀".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        write_bookmarks(&bookmarks, &file).unwrap();
+
+        let loaded = read_bookmarks(&file).unwrap();
+
+        assert_eq!(loaded, bookmarks);
+    }
+
+    #[test]
+    fn canonicalized_paths_deduplicate() {
+        let temp = tempfile::tempdir().unwrap();
+
+        let canonical = temp.path().canonicalize().unwrap();
+
+        let alternative = canonical.join("..").join(canonical.file_name().unwrap());
+
+        assert_eq!(canonical, alternative.canonicalize().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_bookmark_is_same_location() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+
+        let target = root.join("target");
+        let link = root.join("link");
+        fs::create_dir_all(&target).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        assert!(same_location(&link, &target));
+        assert!(same_location(&target.join(""), &target));
+        assert!(!same_location(&root, &target));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_kept_on_request() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+
+        let target = root.join("target");
+        let link = root.join("link");
+        fs::create_dir_all(&target).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let path = link.join(".").join("..").join("link").join("");
+        assert_eq!(bookmark_path(&path, false).unwrap(), target);
+        assert_eq!(bookmark_path(&path, true).unwrap(), link);
+        assert!(bookmark_path(&root.join("missing"), true).is_err());
+    }
+
+    #[test]
+    fn deeper_dirs_stop_at_depth() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("a").join("b").join("c")).unwrap();
+        fs::create_dir_all(root.join("d").join("e")).unwrap();
+
+        let deeper: Vec<_> = deeper_dirs(root.to_path_buf(), 2, false).collect();
+        assert_eq!(deeper, [root.join("a").join("b"), root.join("d").join("e")]);
+
+        assert_eq!(deeper_dirs(root.to_path_buf(), 3, false).count(), 3);
+        assert_eq!(deeper_dirs(root.to_path_buf(), 1, false).count(), 0);
+    }
+
+    #[test]
+    fn relative_paths_go_up_where_needed() {
+        let base = Path::new("/src/app");
+
+        assert_eq!(
+            relative_to(base, Path::new("/src/app/api")),
+            Path::new("api")
+        );
+        assert_eq!(
+            relative_to(base, Path::new("/src/lib")),
+            Path::new("../lib")
+        );
+        assert_eq!(relative_to(base, Path::new("/")), Path::new("../.."));
+        assert_eq!(relative_to(base, base), Path::new("."));
+    }
+}
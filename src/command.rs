@@ -0,0 +1,108 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::error::{AppError, AppResult};
+
+/// Builds a command from a whitespace separated command line.
+pub fn from_line(line: &str) -> AppResult<Command> {
+    let mut words = line.split_whitespace();
+    let mut command = Command::new(words.next().ok_or(AppError::EmptyCommand)?);
+    command.args(words);
+    Ok(command)
+}
+
+/// Builds a command from a whitespace separated template like
+/// `git -C {} status`, substituting `{}` with `path`. The path is appended
+/// when the template has no `{}`.
+pub fn from_template(template: &str, path: &Path) -> AppResult<Command> {
+    let mut words = template.split_whitespace();
+    let program = words.next().ok_or(AppError::EmptyCommand)?;
+
+    let mut command = Command::new(program);
+    let mut substituted = false;
+
+    for word in words {
+        if word.contains("{}") {
+            command.arg(word.replace("{}", &path.to_string_lossy()));
+            substituted = true;
+        } else {
+            command.arg(word);
+        }
+    }
+
+    if !substituted {
+        command.arg(path);
+    }
+
+    Ok(command)
+}
+
+/// Runs `command` with `input` on stdin and returns its stdout.
+pub fn filter(mut command: Command, input: &[u8]) -> AppResult<Vec<u8>> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_vec();
+    // Written from another thread so a command producing output before
+    // consuming all input cannot deadlock.
+    let writer = thread::spawn(move || stdin.write_all(&input));
+
+    let output = child.wait_with_output()?;
+    writer.join().expect("stdin writer panicked")?;
+
+    if !output.status.success() {
+        let program = command.get_program().to_string_lossy().into_owned();
+        return Err(AppError::CommandFailed(program, output.status));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Runs `command`, failing if it does not exit successfully.
+pub fn run(mut command: Command) -> AppResult<()> {
+    let status = command.status()?;
+
+    if !status.success() {
+        let program = command.get_program().to_string_lossy().into_owned();
+        return Err(AppError::CommandFailed(program, status));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<String> {
+        command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn substitutes_placeholder() {
+        let command = from_template("git -C {} status", Path::new("/src/app")).unwrap();
+
+        assert_eq!(command.get_program(), "git");
+        assert_eq!(args(&command), ["-C", "/src/app", "status"]);
+    }
+
+    #[test]
+    fn appends_path_without_placeholder() {
+        let command = from_template("code --new-window", Path::new("/src/app")).unwrap();
+
+        assert_eq!(args(&command), ["--new-window", "/src/app"]);
+    }
+
+    #[test]
+    fn empty_template_is_an_error() {
+        assert!(from_template("  ", Path::new("/")).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//! `pathmarks complete`, shell completion of a `t` argument. `api/mi`
+//! completes the directories starting with `mi` below the bookmark `t api`
+//! would go to, like zoxide's `z foo/<TAB>`.
+
+use crate::guess::{find_case_insensitive, list_child_dirs, match_bookmarks};
+use crate::matching::Matching;
+use crate::store::Bookmark;
+
+/// Completions of `partial`: the subdirectories it names below a bookmark,
+/// each ending with `/` and starting with what was typed, or else the
+/// bookmarks themselves.
+pub fn complete(partial: &str, bookmarks: &[Bookmark], matching: &Matching) -> Vec<String> {
+    match partial.split_once('/') {
+        Some((head, rest)) if !head.is_empty() && !head.starts_with(['~', '$']) => {
+            subdirectories(head, rest, bookmarks, matching)
+        }
+        _ => bookmarks
+            .iter()
+            .map(|b| b.path.to_string_lossy().into_owned())
+            .collect(),
+    }
+}
+
+fn subdirectories(
+    head: &str,
+    rest: &str,
+    bookmarks: &[Bookmark],
+    matching: &Matching,
+) -> Vec<String> {
+    let base = match bookmarks.iter().find(|b| b.name.as_deref() == Some(head)) {
+        Some(named) => named.path.clone(),
+        None => match match_bookmarks(head, bookmarks, matching) {
+            Some(path) => path,
+            None => return Vec::new(),
+        },
+    };
+
+    // Everything before the last `/` names a directory, the rest starts the
+    // names to complete.
+    let (typed, prefix) = match rest.rsplit_once('/') {
+        Some((dirs, prefix)) => (format!("{head}/{dirs}/"), prefix),
+        None => (format!("{head}/"), rest),
+    };
+    // The trailing `/` keeps the directories from matching fuzzily, a
+    // completed path has to lead where it says.
+    let parent = match typed[head.len() + 1..].trim_end_matches('/') {
+        "" => Some(base),
+        dirs => find_case_insensitive(&base, &format!("{dirs}/"), matching),
+    };
+    let Some(parent) = parent else {
+        return Vec::new();
+    };
+
+    let prefix = prefix.to_lowercase();
+    list_child_dirs(&parent, prefix.starts_with('.'))
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|dir| dir.file_name()?.to_str())
+        .filter(|name| name.to_lowercase().starts_with(&prefix))
+        .map(|name| format!("{typed}{name}/"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn completes_directories_below_bookmarks() {
+        let temp = tempfile::tempdir().unwrap();
+        let api = temp.path().join("services").join("api");
+        fs::create_dir_all(api.join("migrations").join("old")).unwrap();
+        fs::create_dir_all(api.join("Middleware")).unwrap();
+        fs::create_dir_all(api.join("src")).unwrap();
+        fs::create_dir_all(api.join(".cache")).unwrap();
+        fs::write(api.join("mix.exs"), "").unwrap();
+        let bookmarks = [Bookmark::new(api.clone())];
+        let matching = Matching::default();
+
+        assert_eq!(
+            complete("api/mi", &bookmarks, &matching),
+            ["api/Middleware/", "api/migrations/"]
+        );
+        assert_eq!(complete("api/", &bookmarks, &matching).len(), 3);
+        assert_eq!(complete("api/.", &bookmarks, &matching), ["api/.cache/"]);
+        assert_eq!(
+            complete("api/migrations/", &bookmarks, &matching),
+            ["api/migrations/old/"]
+        );
+        assert!(complete("api/nothing/", &bookmarks, &matching).is_empty());
+        assert!(complete("web/", &[], &matching).is_empty());
+
+        assert_eq!(
+            complete("ap", &bookmarks, &matching),
+            [api.to_string_lossy()]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::entries::Entries;
+use crate::error::AppResult;
+use crate::hooks::Hooks;
+use crate::matching::Matching;
+use crate::path_map::PathMapping;
+use crate::recent::Promote;
+use crate::store::{Backend, Encryption};
+use crate::sync::SyncCommands;
+use crate::theme::Theme;
+
+/// User configuration, read from `config.toml` in the pathmarks config
+/// directory. Every setting is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub path_map: Vec<PathMapping>,
+    pub openers: HashMap<String, String>,
+    pub encryption: Option<Encryption>,
+    pub backend: Backend,
+    /// Levels below every bookmark `guess` searches when nothing matches.
+    pub search_depth: usize,
+    pub matching: Matching,
+    /// Bookmark paths through symlinks as they are, instead of where they
+    /// point.
+    pub keep_symlinks: bool,
+    /// Translate between the `/mnt/c/...` paths of WSL and the `C:\...`
+    /// paths of Windows, for a store shared by both.
+    pub wsl: bool,
+    /// What `list` and `pick` offer when no flag chooses. Without it, `pick`
+    /// offers bookmarks and the directories in the current one, `list` and
+    /// `pick --multi` only bookmarks.
+    pub entries: Option<Entries>,
+    /// Offer hidden directories of the current directory in `list` and
+    /// `pick`.
+    pub hidden: bool,
+    /// Levels below the current directory `pick` offers directories from,
+    /// 1 by default.
+    pub merge_depth: Option<usize>,
+    pub theme: Theme,
+    /// Commands `sync` copies the store with instead of using git.
+    pub sync: Option<SyncCommands>,
+    pub hooks: Hooks,
+    /// Bookmark directories the shell hook records often.
+    pub promote: Option<Promote>,
+    /// Kilobytes the store, the usage files next to it and the log may take
+    /// before `doctor` warns, 1024 by default.
+    pub max_file_kb: Option<u64>,
+}
+
+impl Config {
+    pub fn load() -> AppResult<Self> {
+        let Some(file) = config_file() else {
+            return Ok(Self::default());
+        };
+
+        match fs::read_to_string(file) {
+            Ok(content) => Ok(toml::from_str(&content)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+fn config_file() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("pathmarks").join("config.toml"))
+}
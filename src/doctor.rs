@@ -0,0 +1,309 @@
+//! Checks of the store run by `pathmarks doctor`, and repairs of what can be
+//! fixed without losing bookmarks.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::path_map::{bookmark_path, expand};
+use crate::remote::is_remote;
+use crate::store::Bookmark;
+
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    /// A line of the text store that does not parse, numbered from 1.
+    Unreadable {
+        line: usize,
+        reason: String,
+    },
+    Relative(PathBuf),
+    MissingParent(PathBuf),
+    NotCanonical {
+        path: PathBuf,
+        canonical: PathBuf,
+    },
+    Duplicate(PathBuf),
+    /// The store file can be written by others or not be read by its owner.
+    Permissions {
+        mode: u32,
+    },
+    /// A file read on every command, or growing with every one, takes more
+    /// than the configured size.
+    TooLarge {
+        file: PathBuf,
+        size: u64,
+    },
+}
+
+impl Problem {
+    /// Whether `doctor --fix` repairs it.
+    pub fn fixable(&self) -> bool {
+        !matches!(
+            self,
+            Problem::Relative(_) | Problem::MissingParent(_) | Problem::TooLarge { .. }
+        )
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::Unreadable { line, reason } => {
+                write!(f, "Line {line} is unreadable: {reason}")
+            }
+            Problem::Relative(path) => write!(f, "{} is a relative path", path.display()),
+            Problem::MissingParent(path) => {
+                write!(f, "The parent of {} does not exist", path.display())
+            }
+            Problem::NotCanonical { path, canonical } => write!(
+                f,
+                "{} is not canonical, it is {}",
+                path.display(),
+                canonical.display()
+            ),
+            Problem::Duplicate(path) => {
+                write!(f, "{} is bookmarked more than once", path.display())
+            }
+            Problem::Permissions { mode } => write!(
+                f,
+                "The store has mode {mode:o}, it should only be writable by its owner"
+            ),
+            Problem::TooLarge { file, size } => write!(
+                f,
+                "{} takes {} KB, which slows pathmarks down",
+                file.display(),
+                size / 1024
+            ),
+        }
+    }
+}
+
+/// Lines of a text store that are not valid UTF-8 or have fields the store
+/// does not understand.
+pub fn unreadable_lines(content: &[u8]) -> Vec<Problem> {
+    content
+        .split(|&b| b == b'\n')
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let reason = line_problem(line)?;
+            Some(Problem::Unreadable {
+                line: i + 1,
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// `content` without the lines of [`unreadable_lines`].
+pub fn readable_content(content: &[u8]) -> String {
+    content
+        .split(|&b| b == b'\n')
+        .filter(|line| line_problem(line).is_none())
+        .filter_map(|line| std::str::from_utf8(line).ok())
+        .map(|line| format!("{line}\n"))
+        .collect()
+}
+
+fn line_problem(line: &[u8]) -> Option<String> {
+    let Ok(line) = std::str::from_utf8(line) else {
+        return Some("not valid UTF-8".to_string());
+    };
+
+    Bookmark::malformed_field(line.trim()).map(|field| format!("unknown field `{field}`"))
+}
+
+pub fn check_bookmarks(bookmarks: &[Bookmark], keep_symlinks: bool) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut seen = HashSet::new();
+
+    for bookmark in bookmarks {
+        if is_remote(&bookmark.path) {
+            if !seen.insert(bookmark.path.clone()) {
+                problems.push(Problem::Duplicate(bookmark.path.clone()));
+            }
+            continue;
+        }
+        let local = local_path(&bookmark.path);
+        if !local.is_absolute() {
+            problems.push(Problem::Relative(bookmark.path.clone()));
+            continue;
+        }
+        if local.parent().is_some_and(|parent| !parent.exists()) {
+            problems.push(Problem::MissingParent(bookmark.path.clone()));
+        }
+
+        let canonical = canonical(&bookmark.path, keep_symlinks);
+        if let Some(canonical) = &canonical
+            && *canonical != bookmark.path
+        {
+            problems.push(Problem::NotCanonical {
+                path: bookmark.path.clone(),
+                canonical: canonical.clone(),
+            });
+        }
+        if !seen.insert(canonical.unwrap_or_else(|| bookmark.path.clone())) {
+            problems.push(Problem::Duplicate(bookmark.path.clone()));
+        }
+    }
+
+    problems
+}
+
+/// `bookmarks` with paths made canonical and duplicates merged into the
+/// first of them.
+pub fn repair(bookmarks: Vec<Bookmark>, keep_symlinks: bool) -> Vec<Bookmark> {
+    let mut repaired: Vec<Bookmark> = Vec::new();
+
+    for mut bookmark in bookmarks {
+        if let Some(canonical) = canonical(&bookmark.path, keep_symlinks) {
+            bookmark.path = canonical;
+        }
+        match repaired.iter_mut().find(|b| b.path == bookmark.path) {
+            Some(existing) => existing.merge(bookmark),
+            None => repaired.push(bookmark),
+        }
+    }
+
+    repaired
+}
+
+/// The canonical form of a bookmarked directory that exists. Paths written
+/// with `~` or variables are left as the user wrote them.
+fn canonical(path: &Path, keep_symlinks: bool) -> Option<PathBuf> {
+    if local_path(path) != path {
+        return None;
+    }
+    bookmark_path(path, keep_symlinks).ok()
+}
+
+fn local_path(path: &Path) -> PathBuf {
+    expand(&path.to_string_lossy())
+}
+
+#[cfg(unix)]
+pub fn check_permissions(file: &Path) -> Option<Problem> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(file).ok()?.permissions().mode() & 0o777;
+    (mode & 0o022 != 0 || mode & 0o600 != 0o600).then_some(Problem::Permissions { mode })
+}
+
+#[cfg(not(unix))]
+pub fn check_permissions(_file: &Path) -> Option<Problem> {
+    None
+}
+
+/// Makes `file` readable and writable by its owner only.
+#[cfg(unix)]
+pub fn fix_permissions(file: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(file)?.permissions().mode();
+    fs::set_permissions(file, fs::Permissions::from_mode((mode | 0o600) & !0o022))
+}
+
+#[cfg(not(unix))]
+pub fn fix_permissions(_file: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// The `files` larger than `limit` bytes.
+pub fn check_sizes(files: &[PathBuf], limit: u64) -> Vec<Problem> {
+    files
+        .iter()
+        .filter_map(|file| {
+            let size = fs::metadata(file).ok()?.len();
+            (size > limit).then(|| Problem::TooLarge {
+                file: file.clone(),
+                size,
+            })
+        })
+        .collect()
+}
+
+/// Copies `file` next to it with a `.bak` suffix.
+pub fn backup(file: &Path) -> io::Result<PathBuf> {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    let backup = file.with_file_name(name);
+
+    fs::copy(file, &backup)?;
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_unreadable_lines() {
+        let content = b"/a\tname=a\n/b\tcolour=red\n\n/c\tvisits=many\n\xff\n";
+
+        let lines: Vec<_> = unreadable_lines(content)
+            .iter()
+            .map(|problem| match problem {
+                Problem::Unreadable { line, .. } => *line,
+                _ => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(lines, [2, 4, 5]);
+        assert_eq!(readable_content(content), "/a\tname=a\n\n\n");
+    }
+
+    #[test]
+    fn finds_large_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let (small, large) = (temp.path().join("small"), temp.path().join("large"));
+        fs::write(&small, [0; 10]).unwrap();
+        fs::write(&large, [0; 2048]).unwrap();
+        let missing = temp.path().join("missing");
+
+        assert_eq!(
+            check_sizes(&[small, large.clone(), missing], 1024),
+            [Problem::TooLarge {
+                file: large,
+                size: 2048
+            }]
+        );
+    }
+
+    #[test]
+    fn repairs_duplicates_and_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let dir = root.join("dir");
+        fs::create_dir(&dir).unwrap();
+
+        let bookmarks = vec![
+            Bookmark {
+                name: Some("dir".to_string()),
+                ..Bookmark::new(dir.join(".."))
+            },
+            Bookmark::new(root.clone()),
+            Bookmark::new(PathBuf::from("relative")),
+            Bookmark::new(PathBuf::from("/nonexistent/pathmarks/dir")),
+        ];
+
+        let problems = check_bookmarks(&bookmarks, false);
+        assert_eq!(
+            problems,
+            [
+                Problem::NotCanonical {
+                    path: dir.join(".."),
+                    canonical: root.clone(),
+                },
+                Problem::Duplicate(root.clone()),
+                Problem::Relative(PathBuf::from("relative")),
+                Problem::MissingParent(PathBuf::from("/nonexistent/pathmarks/dir")),
+            ]
+        );
+
+        let repaired = repair(bookmarks, false);
+        assert_eq!(repaired.len(), 3);
+        assert_eq!(repaired[0].path, root);
+        assert_eq!(repaired[0].name.as_deref(), Some("dir"));
+    }
+}
@@ -0,0 +1,138 @@
+//! Editing the store by hand in `$VISUAL` or `$EDITOR`.
+//!
+//! The bookmarks are written to a private temporary text file in the store
+//! format, and only saved back once every edited line is valid. The file is
+//! removed again unless the edits were invalid, so they are not lost.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+
+use crate::age::now;
+use crate::command::{from_line, run};
+use crate::error::{AppError, AppResult};
+use crate::remote::{self, is_remote};
+use crate::store::{Bookmark, Store, format_bookmarks, parse_bookmarks};
+use crate::undo::Journal;
+
+pub fn edit(store: &dyn Store, journal: &Journal) -> AppResult<()> {
+    // Only readable by the user, as it holds an encrypted store decrypted.
+    let mut file = tempfile::Builder::new()
+        .prefix("pathmarks-")
+        .suffix(".txt")
+        .tempfile()?;
+    file.write_all(format_bookmarks(&store.read()?).as_bytes())?;
+    file.flush()?;
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut command = from_line(&editor)?;
+    command.arg(file.path());
+    run(command)?;
+
+    let edited = parse_bookmarks(&fs::read_to_string(file.path())?);
+    let bookmarks = match normalize(edited) {
+        Ok(bookmarks) => bookmarks,
+        Err(lines) => {
+            let (_, file) = file.keep().map_err(|err| err.error)?;
+            return Err(AppError::InvalidEdit { file, lines });
+        }
+    };
+
+    let _lock = store.lock()?;
+    journal.write(store, "edit", now(), &bookmarks)
+}
+
+/// Cleans up paths and merges bookmarks of the same path, or returns the
+/// paths that are not absolute. `ssh://` bookmarks are kept as they are, and
+/// paths starting with `~` or a variable are expanded when they are used.
+fn normalize(edited: Vec<Bookmark>) -> Result<Vec<Bookmark>, Vec<String>> {
+    let valid = |b: &Bookmark| match b.path.to_str() {
+        Some(path) if is_remote(&b.path) => remote::parse(path).is_some(),
+        Some(path) if path.starts_with(['~', '$']) => true,
+        _ => b.path.is_absolute(),
+    };
+    let invalid: Vec<_> = edited
+        .iter()
+        .filter(|b| !valid(b))
+        .map(|b| b.path.to_string_lossy().into_owned())
+        .collect();
+    if !invalid.is_empty() {
+        return Err(invalid);
+    }
+
+    let mut bookmarks: Vec<Bookmark> = Vec::new();
+    for bookmark in edited {
+        // `components` would turn `ssh://host` into `ssh:/host`.
+        let bookmark = match is_remote(&bookmark.path) {
+            true => bookmark,
+            false => Bookmark {
+                path: bookmark.path.components().collect(),
+                ..bookmark
+            },
+        };
+        match bookmarks.iter_mut().find(|b| b.path == bookmark.path) {
+            Some(existing) => existing.merge(bookmark),
+            None => bookmarks.push(bookmark),
+        }
+    }
+
+    Ok(bookmarks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn rejects_relative_paths() {
+        let edited = parse_bookmarks("/ok\nsrc/relative\n./also\n");
+
+        assert_eq!(
+            normalize(edited),
+            Err(vec!["src/relative".to_string(), "./also".to_string()])
+        );
+    }
+
+    #[test]
+    fn keeps_paths_to_expand() {
+        let edited = parse_bookmarks("~/src/\n$WORK/api\n");
+
+        let expected = [
+            Bookmark::new(PathBuf::from("~/src")),
+            Bookmark::new(PathBuf::from("$WORK/api")),
+        ];
+        assert_eq!(normalize(edited).unwrap(), expected);
+    }
+
+    #[test]
+    fn keeps_remote_bookmarks() {
+        let edited = parse_bookmarks("ssh://devbox/home/me/proj\n/src\nssh://devbox\n");
+        assert_eq!(normalize(edited), Err(vec!["ssh://devbox".to_string()]));
+
+        let edited = parse_bookmarks("ssh://devbox/home/me/proj\n/src\n");
+        let expected = [
+            Bookmark::new(PathBuf::from("ssh://devbox/home/me/proj")),
+            Bookmark::new(PathBuf::from("/src")),
+        ];
+        assert_eq!(normalize(edited).unwrap(), expected);
+    }
+
+    #[test]
+    fn merges_duplicates() {
+        let edited = parse_bookmarks("/src/\tname=src\n/home\n/src/./\ticon=x\n");
+
+        let expected = [
+            Bookmark {
+                name: Some("src".to_string()),
+                icon: Some("x".to_string()),
+                ..Bookmark::new(PathBuf::from("/src"))
+            },
+            Bookmark::new(PathBuf::from("/home")),
+        ];
+        assert_eq!(normalize(edited).unwrap(), expected);
+    }
+}
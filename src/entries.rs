@@ -0,0 +1,55 @@
+//! What `list` and `pick` offer: saved bookmarks, the directories in the
+//! current directory, or both.
+
+use clap::{Args, ValueEnum};
+use serde::Deserialize;
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Entries {
+    /// Bookmarks and the directories in the current directory
+    Merged,
+    Bookmarks,
+    /// The directories in the current directory
+    Dirs,
+}
+
+impl Entries {
+    pub fn bookmarks(self) -> bool {
+        self != Entries::Dirs
+    }
+
+    pub fn dirs(self) -> bool {
+        self != Entries::Bookmarks
+    }
+}
+
+/// Overrides the `entries` setting of the config for one command.
+#[derive(Args, Clone, Copy, Debug)]
+#[group(multiple = false)]
+pub struct EntryFlags {
+    /// Only saved bookmarks, without the directories in the current one
+    #[arg(long, visible_alias = "no-merge")]
+    bookmarks_only: bool,
+    /// Only the directories in the current directory
+    #[arg(long)]
+    dirs_only: bool,
+    /// Saved bookmarks and the directories in the current directory
+    #[arg(long)]
+    merge: bool,
+}
+
+impl EntryFlags {
+    /// The entries chosen on the command line, or else `fallback`.
+    pub fn or(self, fallback: Entries) -> Entries {
+        if self.bookmarks_only {
+            Entries::Bookmarks
+        } else if self.dirs_only {
+            Entries::Dirs
+        } else if self.merge {
+            Entries::Merged
+        } else {
+            fallback
+        }
+    }
+}
@@ -1,22 +1,181 @@
+//! The errors of pathmarks, each with the exit code the binary uses.
+
 use std::io;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+use nucleo_picker::error::PickError;
 use thiserror::Error;
 
-pub(crate) type AppResult<T> = Result<T, AppError>;
+pub type AppResult<T> = Result<T, AppError>;
 
+/// Everything that can go wrong in pathmarks.
 #[derive(Debug, Error)]
-pub(crate) enum AppError {
+pub enum AppError {
     #[error("Could not determine data directory")]
     DataDirectoryNotFound,
 
+    #[error("Could not determine home directory")]
+    HomeDirectoryNotFound,
+
     #[error(transparent)]
     Io(#[from] io::Error),
 
+    #[error("Invalid config: {0}")]
+    Config(#[from] toml::de::Error),
+
+    #[cfg(feature = "sqlite")]
     #[error(transparent)]
-    Picker(#[from] nucleo_picker::error::PickError),
+    Sqlite(#[from] rusqlite::Error),
+
+    #[cfg(not(feature = "sqlite"))]
+    #[error("pathmarks was built without the sqlite feature")]
+    SqliteUnsupported,
+
+    #[error("Encryption is only supported by the text backend")]
+    EncryptionUnsupported,
+
+    #[error("Bookmarks file version {0} is newer than this pathmarks supports")]
+    UnsupportedStoreVersion(u32),
+
+    #[error(transparent)]
+    Picker(PickError),
+
+    #[error("Cancelled")]
+    Cancelled,
+
+    #[error("{0} needs a terminal")]
+    NotInteractive(&'static str),
+
+    #[error("Opening a tmux window needs to run inside tmux, use --session outside")]
+    NotInTmux,
 
     #[error("Path must be absolute")]
     InvalidPath,
 
+    #[error("Remote bookmarks look like ssh://host/absolute/path, not {0}")]
+    InvalidRemote(String),
+
+    #[error("Metadata cannot contain tabs or newlines")]
+    InvalidMetadata,
+
+    #[error("Tags cannot be empty or contain commas or whitespace: {0}")]
+    InvalidTag(String),
+
+    #[error("Invalid profile name: {0}")]
+    InvalidProfile(String),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid export row: {0}")]
+    InvalidExport(String),
+
+    #[error("Paths must be absolute or start with ~ or $, nothing was saved: {}. Your edits are in {}", lines.join(", "), file.display())]
+    InvalidEdit { file: PathBuf, lines: Vec<String> },
+
+    #[error("{option} is not supported for {shell}")]
+    ShellUnsupported {
+        option: &'static str,
+        shell: &'static str,
+    },
+
+    #[error("Not inside a git repository")]
+    NotInRepository,
+
+    #[error("No version control root or workspace manifest above the current directory")]
+    NoProjectRoot,
+
+    #[error("Command is empty")]
+    EmptyCommand,
+
+    #[error("{0} failed: {1}")]
+    CommandFailed(String, ExitStatus),
+
+    /// A program run by `exec`, whose exit code is passed on.
+    #[error("{0} failed: {1}")]
+    Exec(String, ExitStatus),
+
+    #[error("Sync through git only works with the text backend")]
+    SyncUnsupported,
+
+    #[error("--remote is for git, but sync uses the commands in the config")]
+    SyncRemoteWithCommands,
+
+    #[error("Sync through git only works for stores in {0}")]
+    SyncOutsideDataDir(String),
+
+    #[cfg(unix)]
+    #[error("The daemon failed: {0}")]
+    Daemon(String),
+
+    #[cfg(unix)]
+    #[error("A daemon is already listening on {}", .0.display())]
+    DaemonRunning(PathBuf),
+
+    #[cfg(not(unix))]
+    #[error("The daemon needs Unix sockets, which this platform does not have")]
+    DaemonUnsupported,
+
+    #[cfg(feature = "watch")]
+    #[error("Could not watch the bookmarks: {0}")]
+    Watch(#[from] notify::Error),
+
+    #[cfg(all(unix, not(feature = "watch")))]
+    #[error("pathmarks was built without the watch feature")]
+    WatchUnsupported,
+
     #[error("Not found: {0}")]
     NotFound(String),
+
+    #[error("Not found: {0}. Did you mean {nearest}?", nearest = .1.join(", "))]
+    NotFoundSuggestions(String, Vec<String>),
+}
+
+impl From<PickError> for AppError {
+    fn from(err: PickError) -> Self {
+        match err {
+            PickError::UserInterrupted => AppError::Cancelled,
+            err => AppError::Picker(err),
+        }
+    }
+}
+
+impl AppError {
+    /// The exit code reporting this error. Shell integrations rely on these,
+    /// so they only change with a major version:
+    ///
+    /// - 1 for errors without a code of their own
+    /// - 2 for invalid command line arguments, reported by clap
+    /// - 3 when nothing matched
+    /// - 4 when reading or writing files failed
+    /// - 130 when the picker was closed without picking
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            AppError::Cancelled => 130,
+            AppError::NotFound(_) | AppError::NotFoundSuggestions(..) => 3,
+            AppError::Io(_) | AppError::Picker(PickError::IO(_)) => 4,
+            #[cfg(feature = "sqlite")]
+            AppError::Sqlite(_) => 4,
+            AppError::Exec(_, status) => status
+                .code()
+                .and_then(|code| u8::try_from(code).ok())
+                .filter(|code| *code != 0)
+                .unwrap_or(1),
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(AppError::from(PickError::UserInterrupted).exit_code(), 130);
+        assert_eq!(AppError::NotFound("src".to_string()).exit_code(), 3);
+        assert_eq!(AppError::from(io::Error::other("disk")).exit_code(), 4);
+        assert_eq!(AppError::InvalidPath.exit_code(), 1);
+    }
 }
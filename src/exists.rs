@@ -0,0 +1,106 @@
+//! Existence checks that run concurrently and give up after a timeout, since
+//! checking a path on a slow disk or a dead network mount can take minutes.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::remote::is_remote;
+
+/// Checks are waiting on the disk, not the CPU, so there can be more of
+/// them than cores.
+const WORKERS: usize = 16;
+
+/// Checks whether each of `paths` exists, on several threads at once, and
+/// calls `done` with the index and result of each check as it finishes, see
+/// [`exists_within`]. Stops early when `done` returns false.
+pub fn check_all(
+    paths: &[PathBuf],
+    timeout: Option<Duration>,
+    mut done: impl FnMut(usize, Option<bool>) -> bool,
+) {
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..WORKERS.min(paths.len()) {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = paths.get(i) else {
+                        break;
+                    };
+                    // Sending fails once the caller stopped early.
+                    if sender.send((i, exists_within(path, timeout))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        for (i, exists) in receiver {
+            if !done(i, exists) {
+                break;
+            }
+        }
+    });
+}
+
+/// Whether `path` exists, or `None` when checking took longer than
+/// `timeout`. The check keeps running in the background until the process
+/// exits. Remote bookmarks cannot be checked and count as existing.
+pub fn exists_within(path: &Path, timeout: Option<Duration>) -> Option<bool> {
+    if is_remote(path) {
+        return Some(true);
+    }
+    let Some(timeout) = timeout else {
+        return Some(path.exists());
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let path: PathBuf = path.to_path_buf();
+    thread::spawn(move || sender.send(path.exists()));
+
+    receiver.recv_timeout(timeout).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_every_path_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let paths: Vec<_> = (0..40).map(|i| temp.path().join(i.to_string())).collect();
+        for path in paths.iter().step_by(2) {
+            std::fs::create_dir(path).unwrap();
+        }
+
+        let mut results = vec![None; paths.len()];
+        check_all(&paths, None, |i, exists| {
+            assert!(results[i].replace(exists).is_none());
+            true
+        });
+
+        let expected: Vec<_> = (0..40).map(|i| Some(Some(i % 2 == 0))).collect();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn checks_with_and_without_timeout() {
+        let temp = tempfile::tempdir().unwrap();
+        let timeout = Some(Duration::from_secs(5));
+
+        assert_eq!(exists_within(temp.path(), timeout), Some(true));
+        assert_eq!(
+            exists_within(&temp.path().join("missing"), timeout),
+            Some(false)
+        );
+        assert_eq!(exists_within(temp.path(), None), Some(true));
+    }
+}
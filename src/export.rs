@@ -0,0 +1,191 @@
+//! Dumps the whole store, metadata included, and reads such dumps back.
+
+use clap::ValueEnum;
+
+use crate::error::{AppError, AppResult};
+use crate::store::{Bookmark, format_bookmarks, parse_bookmarks};
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum ExportFormat {
+    /// The format of the bookmarks text file
+    Plain,
+    Json,
+    Csv,
+}
+
+const CSV_HEADER: &str = "path,name,icon,opener,last_used,visits,tags,created_at,note,pinned,slot";
+/// Exports from before slots existed.
+const CSV_HEADER_WITHOUT_SLOT: &str =
+    "path,name,icon,opener,last_used,visits,tags,created_at,note,pinned";
+
+pub fn export(bookmarks: &[Bookmark], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Plain => format_bookmarks(bookmarks),
+        ExportFormat::Json => {
+            serde_json::to_string_pretty(bookmarks).expect("bookmarks serialize to json")
+        }
+        ExportFormat::Csv => {
+            let rows = bookmarks.iter().map(|b| {
+                let fields = [
+                    b.path.to_string_lossy().into_owned(),
+                    b.name.clone().unwrap_or_default(),
+                    b.icon.clone().unwrap_or_default(),
+                    b.opener.clone().unwrap_or_default(),
+                    b.last_used.map(|v| v.to_string()).unwrap_or_default(),
+                    b.visits.map(|v| v.to_string()).unwrap_or_default(),
+                    b.tags.join(","),
+                    b.created_at.map(|v| v.to_string()).unwrap_or_default(),
+                    b.note.clone().unwrap_or_default(),
+                    b.pinned.to_string(),
+                    b.slot.map(|v| v.to_string()).unwrap_or_default(),
+                ];
+                let fields: Vec<_> = fields.iter().map(|f| csv_quote(f)).collect();
+                fields.join(",") + "\n"
+            });
+
+            std::iter::once(format!("{CSV_HEADER}\n"))
+                .chain(rows)
+                .collect()
+        }
+    }
+}
+
+/// Reads an export of any format, telling them apart by their first line.
+pub fn parse_export(content: &str) -> AppResult<Vec<Bookmark>> {
+    if content.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(content)?);
+    }
+
+    match content.split_once('\n') {
+        Some((header, rows))
+            if [CSV_HEADER, CSV_HEADER_WITHOUT_SLOT].contains(&header.trim_end()) =>
+        {
+            parse_csv(rows)
+        }
+        _ => Ok(parse_bookmarks(content)),
+    }
+}
+
+fn csv_quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn parse_csv(rows: &str) -> AppResult<Vec<Bookmark>> {
+    let mut bookmarks = Vec::new();
+    let mut chars = rows.chars().peekable();
+
+    while chars.peek().is_some() {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = !quoted,
+                ',' if !quoted => fields.push(std::mem::take(&mut field)),
+                '\n' if !quoted => break,
+                '\r' if !quoted => {}
+                c => field.push(c),
+            }
+        }
+        fields.push(field);
+
+        if fields.iter().all(String::is_empty) {
+            continue;
+        }
+        bookmarks.push(csv_bookmark(fields)?);
+    }
+
+    Ok(bookmarks)
+}
+
+fn csv_bookmark(mut fields: Vec<String>) -> AppResult<Bookmark> {
+    if fields.len() == 10 {
+        fields.push(String::new());
+    }
+
+    let [
+        path,
+        name,
+        icon,
+        opener,
+        last_used,
+        visits,
+        tags,
+        created_at,
+        note,
+        pinned,
+        slot,
+    ]: [String; 11] = fields
+        .try_into()
+        .map_err(|fields: Vec<String>| AppError::InvalidExport(fields.join(",")))?;
+
+    let text = |s: String| (!s.is_empty()).then_some(s);
+    let number = |s: String| s.parse().ok();
+
+    Ok(Bookmark {
+        path: path.into(),
+        name: text(name),
+        icon: text(icon),
+        opener: text(opener),
+        last_used: number(last_used),
+        visits: number(visits),
+        tags: tags
+            .split(',')
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect(),
+        created_at: number(created_at),
+        note: text(note),
+        pinned: pinned == "true",
+        slot: slot.parse().ok(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn every_format_roundtrips() {
+        let bookmarks = [
+            Bookmark {
+                path: PathBuf::from("/src/with, comma"),
+                name: Some("api".to_string()),
+                tags: vec!["work".to_string(), "rust".to_string()],
+                visits: Some(4),
+                note: Some("say \"hi\"".to_string()),
+                pinned: true,
+                slot: Some(3),
+                ..Default::default()
+            },
+            Bookmark::new(PathBuf::from("/home/me")),
+        ];
+
+        for format in [ExportFormat::Plain, ExportFormat::Json, ExportFormat::Csv] {
+            let exported = export(&bookmarks, format);
+            assert_eq!(parse_export(&exported).unwrap(), bookmarks, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn csv_without_slot_column() {
+        let csv = format!("{CSV_HEADER_WITHOUT_SLOT}\n/src,,,,,,,,,true\n");
+
+        let bookmarks = parse_export(&csv).unwrap();
+
+        assert_eq!(bookmarks[0].path, PathBuf::from("/src"));
+        assert!(bookmarks[0].pinned);
+        assert_eq!(bookmarks[0].slot, None);
+    }
+}
@@ -0,0 +1,75 @@
+//! Structured output of `list --format`.
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::frecency::frecency;
+use crate::store::Bookmark;
+
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+pub enum Format {
+    #[default]
+    Plain,
+    Json,
+}
+
+#[derive(Serialize)]
+struct JsonBookmark<'a> {
+    path: &'a str,
+    tags: &'a [String],
+    alias: Option<&'a str>,
+    /// Seconds since the unix epoch.
+    last_visit: Option<u64>,
+    score: f64,
+}
+
+/// An array with one object per bookmark, with absolute paths.
+pub fn json(bookmarks: &[Bookmark], now: u64) -> String {
+    let paths: Vec<_> = bookmarks.iter().map(|b| b.path.to_string_lossy()).collect();
+
+    let out: Vec<_> = bookmarks
+        .iter()
+        .zip(&paths)
+        .map(|(bookmark, path)| JsonBookmark {
+            path,
+            tags: &bookmark.tags,
+            alias: bookmark.name.as_deref(),
+            last_visit: bookmark.last_used,
+            score: frecency(bookmark, now),
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&out).expect("bookmarks serialize to json")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn json_objects() {
+        let bookmarks = [Bookmark {
+            path: PathBuf::from("/src/api"),
+            name: Some("api".to_string()),
+            tags: vec!["work".to_string()],
+            last_used: Some(100),
+            visits: Some(2),
+            ..Default::default()
+        }];
+
+        let value: serde_json::Value = serde_json::from_str(&json(&bookmarks, 160)).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!([{
+                "path": "/src/api",
+                "tags": ["work"],
+                "alias": "api",
+                "last_visit": 100,
+                "score": 8.0,
+            }])
+        );
+    }
+}
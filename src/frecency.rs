@@ -0,0 +1,120 @@
+//! Ranks bookmarks by how often and how recently they are visited, like
+//! zoxide does. Visits are recorded by the shell hook calling
+//! `pathmarks visit` on every directory change.
+
+use crate::store::Bookmark;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Visits are scaled down once they add up to this many, so old habits fade.
+const MAX_VISITS: u64 = 10_000;
+
+/// Visit count weighted by how long ago the last use was.
+pub fn frecency(bookmark: &Bookmark, now: u64) -> f64 {
+    let Some(visits) = bookmark.visits else {
+        return 0.0;
+    };
+
+    let age = now.saturating_sub(bookmark.last_used.unwrap_or_default());
+    let factor = match age {
+        age if age < HOUR => 4.0,
+        age if age < DAY => 2.0,
+        age if age < WEEK => 0.5,
+        _ => 0.25,
+    };
+
+    visits as f64 * factor
+}
+
+/// Sorts pinned bookmarks first, then by frecency, highest first.
+pub fn sort_by_frecency(bookmarks: &mut [Bookmark], now: u64) {
+    bookmarks.sort_by(|a, b| {
+        b.pinned
+            .cmp(&a.pinned)
+            .then(frecency(b, now).total_cmp(&frecency(a, now)))
+    });
+}
+
+/// Multiplier for a fuzzy match score. Frecency nudges the ranking between
+/// similar matches without overruling a clearly better one.
+pub fn match_weight(bookmark: &Bookmark, now: u64) -> f64 {
+    1.0 + frecency(bookmark, now).ln_1p() / 10.0
+}
+
+/// Counts a visit to `bookmarks[index]`, aging all visit counts when they
+/// grow too large. Counts that age to nothing are dropped, so rarely used
+/// bookmarks do not carry them around.
+pub fn visit(bookmarks: &mut [Bookmark], index: usize, now: u64) {
+    // Aging first keeps the visit being counted from rounding away.
+    let total: u64 = bookmarks.iter().filter_map(|b| b.visits).sum();
+    if total + 1 > MAX_VISITS {
+        for bookmark in bookmarks.iter_mut() {
+            bookmark.visits = bookmark.visits.map(|v| v * 9 / 10).filter(|&v| v > 0);
+        }
+    }
+
+    let bookmark = &mut bookmarks[index];
+    bookmark.visits = Some(bookmark.visits.unwrap_or_default() + 1);
+    bookmark.last_used = Some(now);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn visited(visits: u64, last_used: u64) -> Bookmark {
+        Bookmark {
+            path: PathBuf::from("/p"),
+            visits: Some(visits),
+            last_used: Some(last_used),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn recent_visits_count_more() {
+        let now = 10 * WEEK;
+
+        assert_eq!(frecency(&visited(3, now - 60), now), 12.0);
+        assert_eq!(frecency(&visited(3, now - 2 * HOUR), now), 6.0);
+        assert_eq!(frecency(&visited(3, now - 2 * DAY), now), 1.5);
+        assert_eq!(frecency(&visited(3, now - 2 * WEEK), now), 0.75);
+        assert_eq!(frecency(&Bookmark::new(PathBuf::from("/p")), now), 0.0);
+    }
+
+    #[test]
+    fn pinned_sort_first() {
+        let now = 10 * WEEK;
+        let pinned = Bookmark {
+            path: PathBuf::from("/pinned"),
+            pinned: true,
+            ..Default::default()
+        };
+        let mut bookmarks = vec![visited(1, now), visited(3, now), pinned];
+
+        sort_by_frecency(&mut bookmarks, now);
+
+        assert_eq!(bookmarks[0].path, PathBuf::from("/pinned"));
+        assert_eq!(bookmarks[1].visits, Some(3));
+    }
+
+    #[test]
+    fn visits_age() {
+        let mut bookmarks = vec![
+            visited(MAX_VISITS, 0),
+            Bookmark::new(PathBuf::from("/q")),
+            visited(1, 0),
+        ];
+
+        visit(&mut bookmarks, 1, 100);
+
+        assert_eq!(bookmarks[0].visits, Some(MAX_VISITS * 9 / 10));
+        assert_eq!(bookmarks[2].visits, None);
+        assert_eq!(bookmarks[1].visits, Some(1));
+        assert_eq!(bookmarks[1].last_used, Some(100));
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Finds the root of the git repository containing `dir`.
+pub fn repo_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Finds the root of the project containing `dir`: the closest version
+/// control root, or else the closest directory with a workspace manifest.
+pub fn project_root(dir: &Path) -> Option<PathBuf> {
+    let closest = |markers: &[&str]| {
+        dir.ancestors()
+            .find(|ancestor| markers.iter().any(|m| ancestor.join(m).exists()))
+            .map(Path::to_path_buf)
+    };
+
+    closest(&[".git", ".hg", ".jj"]).or_else(|| closest(&["Cargo.toml", "package.json"]))
+}
+
+/// Derives `org/repo` from the origin remote when `dir` is the root of a git
+/// repository.
+pub fn origin_name(dir: &Path) -> Option<String> {
+    if !dir.join(".git").exists() {
+        return None;
+    }
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    remote_name(String::from_utf8_lossy(&output.stdout).trim())
+}
+
+/// Extracts `org/repo` from remote urls like `git@host:org/repo.git` or
+/// `https://host/org/repo`.
+fn remote_name(url: &str) -> Option<String> {
+    let path = url.trim_end_matches('/').trim_end_matches(".git");
+    let mut segments = path.rsplit(['/', ':']).filter(|s| !s.is_empty());
+
+    let repo = segments.next()?;
+    let org = segments.next()?;
+
+    Some(format!("{org}/{repo}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_root_prefers_version_control() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        let crate_dir = repo.join("crates").join("cli");
+        std::fs::create_dir_all(repo.join(".jj")).unwrap();
+        std::fs::create_dir_all(crate_dir.join("src")).unwrap();
+        std::fs::write(crate_dir.join("Cargo.toml"), "").unwrap();
+
+        assert_eq!(project_root(&crate_dir.join("src")), Some(repo.clone()));
+
+        std::fs::remove_dir(repo.join(".jj")).unwrap();
+        assert_eq!(project_root(&crate_dir.join("src")), Some(crate_dir));
+    }
+
+    #[test]
+    fn remote_urls() {
+        let expected = Some("sofusa/pathmarks".to_string());
+
+        assert_eq!(remote_name("git@github.com:sofusa/pathmarks.git"), expected);
+        assert_eq!(remote_name("https://github.com/sofusa/pathmarks"), expected);
+        assert_eq!(remote_name("ssh://git@host:22/sofusa/pathmarks/"), expected);
+        assert_eq!(remote_name("pathmarks"), None);
+    }
+}
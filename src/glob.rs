@@ -0,0 +1,98 @@
+/// Whether a query component should be matched as a glob instead of fuzzily.
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Case insensitive shell-style wildcard matching supporting `*`, `?` and
+/// bracket expressions like `[abc]`, `[a-z]` and `[!x]`.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(1),
+            Some('[') => match_class(&pattern[p..], name[n]),
+            Some(c) if *c == name[n] => Some(1),
+            _ => None,
+        };
+
+        match step {
+            Some(len) => {
+                p += len;
+                n += 1;
+            }
+            None => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Matches `c` against the bracket expression at the start of `pattern`,
+/// returning the length of the expression on success.
+fn match_class(pattern: &[char], c: char) -> Option<usize> {
+    let end = pattern.iter().skip(2).position(|x| *x == ']')? + 2;
+    let class = &pattern[1..end];
+
+    let (negated, class) = match class.first() {
+        Some('!' | '^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            found |= class[i] <= c && c <= class[i + 2];
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+
+    (found != negated).then_some(end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        assert!(glob_match("cli*", "cli-tools"));
+        assert!(glob_match("*tools", "cli-tools"));
+        assert!(glob_match("c?i*", "CLI"));
+        assert!(!glob_match("cli*", "my-cli"));
+        assert!(glob_match("*", ""));
+    }
+
+    #[test]
+    fn bracket_expressions() {
+        assert!(glob_match("v[0-9]", "v2"));
+        assert!(!glob_match("v[!0-9]", "v2"));
+        assert!(glob_match("[ab]ar", "bar"));
+        assert!(!glob_match("[ab]ar", "car"));
+    }
+
+    #[test]
+    fn unclosed_bracket_does_not_match() {
+        assert!(!glob_match("[ab", "a"));
+    }
+}
@@ -0,0 +1,780 @@
+//! Resolving queries to directories, as `pathmarks guess` does: bookmark
+//! names, the current directory, slots, parent directories, the current
+//! repository and fuzzy matches against all bookmarks.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tracing::debug;
+
+use crate::age::now;
+use crate::frecency::match_weight;
+use crate::git::repo_root;
+use crate::glob::{glob_match, is_glob};
+use crate::matching::Matching;
+use crate::path_map::expand;
+use crate::remote::is_remote;
+use crate::store::Bookmark;
+
+/// Matches scoring at least this share of the best are ambiguous.
+pub const CLOSE_MATCH_RATIO: f64 = 0.95;
+
+/// The best fuzzy match of `query` among bookmark paths, each score
+/// multiplied by `weight` of the path.
+pub fn best_bookmark_match<'a>(
+    query: &str,
+    bookmarks: impl IntoIterator<Item = &'a str>,
+    weight: impl Fn(&str) -> f64,
+    matching: &Matching,
+) -> Option<&'a str> {
+    matching.best_by(query, bookmarks, weight).map(|(s, _)| s)
+}
+
+/// Resolves shell arguments to a directory, the way `pathmarks guess` does.
+/// `search_depth` levels below every bookmark are searched when nothing else
+/// matches, and when nothing matches at all the arguments are joined into a
+/// path as is.
+pub fn guess(
+    paths: &[String],
+    current_dir: &Path,
+    bookmarks: &[Bookmark],
+    search_depth: usize,
+    matching: &Matching,
+) -> PathBuf {
+    let paths: Vec<_> = paths
+        .iter()
+        .map(|p| expand(p).to_string_lossy().into_owned())
+        .collect();
+    let Some(first) = paths.first() else {
+        return PathBuf::new();
+    };
+
+    if Path::new(first).is_absolute() || is_remote(Path::new(first)) {
+        return PathBuf::from(first);
+    }
+
+    let walked = walk_segments(&paths, current_dir, bookmarks, search_depth, matching);
+
+    // `t proj src` walks into `src` below `proj` when it can, otherwise the
+    // terms filter bookmark components in order. Terms given as one
+    // argument, `t 'proj src'`, are always filters.
+    let terms: Vec<_> = paths.iter().flat_map(|p| p.split_whitespace()).collect();
+    let spaced = terms.len() > paths.len();
+    if terms.len() > 1
+        && (spaced || !walked.exists())
+        && let Some(path) = match_components(&terms, bookmarks)
+    {
+        debug!("{terms:?} filter bookmark components to {}", path.display());
+        return path;
+    }
+
+    debug!("{paths:?} walk to {}", walked.display());
+    walked
+}
+
+/// Resolves the first argument, then every next one as a subdirectory of
+/// the previous.
+fn walk_segments(
+    paths: &[String],
+    current_dir: &Path,
+    bookmarks: &[Bookmark],
+    search_depth: usize,
+    matching: &Matching,
+) -> PathBuf {
+    let first = resolve_first(&paths[0], current_dir, bookmarks, matching)
+        .or_else(|| search_below(&paths[0], bookmarks, search_depth, matching));
+    let Some(mut current) = first else {
+        return PathBuf::from(paths.join("/"));
+    };
+
+    for segment in paths.iter().skip(1) {
+        match find_case_insensitive(&current, segment, matching) {
+            Some(next) => current = next,
+            None => return current.join(segment),
+        }
+    }
+
+    current
+}
+
+/// Searches up to `depth` levels below every bookmark for a directory named
+/// like `query`, nearer levels first.
+fn search_below(
+    query: &str,
+    bookmarks: &[Bookmark],
+    depth: usize,
+    matching: &Matching,
+) -> Option<PathBuf> {
+    if query.contains('/') || is_glob(query) {
+        return None;
+    }
+
+    let mut level: Vec<_> = bookmarks.iter().map(|b| b.path.clone()).collect();
+    for _ in 0..depth {
+        level = level
+            .iter()
+            .flat_map(|dir| list_child_dirs(dir, false).unwrap_or_default())
+            .collect();
+
+        let names: Vec<_> = level
+            .iter()
+            .map(|dir| dir.file_name().unwrap_or_default().to_string_lossy())
+            .collect();
+        if let Some((name, _)) = matching.best(query, names.iter().map(|n| n.as_ref())) {
+            let index = names.iter().position(|n| n == name)?;
+            return Some(level[index].clone());
+        }
+    }
+    None
+}
+
+/// The best bookmark whose path components contain the terms in order, the
+/// last term in the last component, like `zoxide`. Lowercase terms ignore
+/// case.
+fn match_components(terms: &[&str], bookmarks: &[Bookmark]) -> Option<PathBuf> {
+    let contains = |component: &str, term: &str| {
+        if term.chars().any(char::is_uppercase) {
+            component.contains(term)
+        } else {
+            component.to_lowercase().contains(term)
+        }
+    };
+    let matches = |path: &Path| {
+        let components: Vec<_> = path
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .collect();
+        let (Some((last_term, terms)), Some((last, components))) =
+            (terms.split_last(), components.split_last())
+        else {
+            return false;
+        };
+
+        let mut remaining = components.iter();
+        contains(last, last_term)
+            && terms
+                .iter()
+                .all(|term| remaining.any(|c| contains(c, term)))
+    };
+
+    let now = now();
+    bookmarks
+        .iter()
+        .filter(|b| matches(&b.path))
+        .max_by(|a, b| {
+            match_weight(a, now)
+                .total_cmp(&match_weight(b, now))
+                .then_with(|| b.path.as_os_str().len().cmp(&a.path.as_os_str().len()))
+        })
+        .map(|b| b.path.clone())
+}
+
+/// Resolves the first guess component: a bookmark named exactly like it
+/// comes first, then the current directory, slots, parent directories, the
+/// enclosing git repository, and finally all bookmarks.
+fn resolve_first(
+    query: &str,
+    current_dir: &Path,
+    bookmarks: &[Bookmark],
+    matching: &Matching,
+) -> Option<PathBuf> {
+    if let Some(named) = bookmarks.iter().find(|b| b.name.as_deref() == Some(query)) {
+        debug!("`{query}` is the name of {}", named.path.display());
+        return Some(named.path.clone());
+    }
+
+    if let Some(path) = find_case_insensitive(current_dir, query, matching) {
+        debug!(
+            "`{query}` matches {} in the current directory",
+            path.display()
+        );
+        return Some(path);
+    }
+
+    if let Some(slotted) = bookmarks
+        .iter()
+        .find(|b| b.slot.is_some_and(|s| s.to_string() == query))
+    {
+        debug!("`{query}` is the slot of {}", slotted.path.display());
+        return Some(slotted.path.clone());
+    }
+
+    if let Some(ancestor) = find_ancestor(current_dir, query) {
+        debug!("`{query}` matches the parent {}", ancestor.display());
+        return Some(ancestor);
+    }
+
+    if let Some(root) = repo_root(current_dir) {
+        let in_repo: Vec<_> = bookmarks
+            .iter()
+            .filter(|b| b.path.starts_with(&root))
+            .cloned()
+            .collect();
+
+        if let Some(path) = match_bookmarks(query, &in_repo, matching) {
+            debug!("`{query}` matches {} in the repository", path.display());
+            return Some(path);
+        }
+        if root != current_dir
+            && let Some(path) = find_case_insensitive(&root, query, matching)
+        {
+            debug!(
+                "`{query}` matches {} in the repository root",
+                path.display()
+            );
+            return Some(path);
+        }
+    }
+
+    if let Some(path) = resolve_subpath(query, bookmarks, matching) {
+        debug!("`{query}` is {} below a bookmark", path.display());
+        return Some(path);
+    }
+
+    let matched = match_bookmarks(query, bookmarks, matching);
+    match &matched {
+        Some(path) => debug!("`{query}` matches the bookmark {}", path.display()),
+        None => debug!("`{query}` matches no bookmark"),
+    }
+    matched
+}
+
+/// The closest parent directory named like `query`, ignoring case, or else
+/// starting with it, like `bd`.
+fn find_ancestor(current_dir: &Path, query: &str) -> Option<PathBuf> {
+    if query.contains('/') || is_glob(query) {
+        return None;
+    }
+    let query = query.to_lowercase();
+    let name = |dir: &Path| dir.file_name().map(|n| n.to_string_lossy().to_lowercase());
+
+    let ancestors = || current_dir.ancestors().skip(1);
+    ancestors()
+        .find(|dir| name(dir).is_some_and(|n| n == query))
+        .or_else(|| ancestors().find(|dir| name(dir).is_some_and(|n| n.starts_with(&query))))
+        .map(Path::to_path_buf)
+}
+
+/// Resolves `api/migrations` as the `migrations` directory below the
+/// bookmark best matching `api`, if it exists.
+fn resolve_subpath(query: &str, bookmarks: &[Bookmark], matching: &Matching) -> Option<PathBuf> {
+    if is_glob(query) {
+        return None;
+    }
+    let (head, rest) = query.split_once('/')?;
+    if head.is_empty() || rest.is_empty() {
+        return None;
+    }
+
+    let base = match bookmarks.iter().find(|b| b.name.as_deref() == Some(head)) {
+        Some(named) => named.path.clone(),
+        None => match_bookmarks(head, bookmarks, matching)?,
+    };
+    find_case_insensitive(&base, rest, matching)
+}
+
+/// The bookmark best matching `query`, by name glob or fuzzily, favouring
+/// frecent bookmarks.
+pub fn match_bookmarks(
+    query: &str,
+    bookmarks: &[Bookmark],
+    matching: &Matching,
+) -> Option<PathBuf> {
+    if is_glob(query) {
+        return glob_bookmark_match(query, bookmarks).map(|b| b.path.clone());
+    }
+
+    let now = now();
+    let weight = |path: &str| {
+        bookmarks
+            .iter()
+            .find(|b| b.path.to_str() == Some(path))
+            .map_or(1.0, |b| match_weight(b, now))
+    };
+
+    let candidates = bookmarks.iter().flat_map(|b| b.path.to_str());
+    best_bookmark_match(query, candidates, weight, matching).map(PathBuf::from)
+}
+
+/// Bookmarks scoring about as well as the best match for `query`, when
+/// there are several.
+pub fn close_matches(query: &str, bookmarks: &[Bookmark], matching: &Matching) -> Vec<Bookmark> {
+    if is_glob(query) {
+        return Vec::new();
+    }
+
+    let now = now();
+    let find = |path: &str| bookmarks.iter().find(|b| b.path.to_str() == Some(path));
+    let weight = |path: &str| find(path).map_or(1.0, |b| match_weight(b, now));
+
+    let candidates = bookmarks.iter().flat_map(|b| b.path.to_str());
+    let ranked = matching.ranked(query, candidates, weight);
+    let Some(&(_, best)) = ranked.first() else {
+        return Vec::new();
+    };
+
+    let close: Vec<_> = ranked
+        .into_iter()
+        .take_while(|(_, score)| *score >= best * CLOSE_MATCH_RATIO)
+        .filter_map(|(path, _)| find(path).cloned())
+        .collect();
+    if close.len() > 1 { close } else { Vec::new() }
+}
+
+/// The first bookmark whose directory name matches the glob `pattern`.
+pub fn glob_bookmark_match<'a>(pattern: &str, bookmarks: &'a [Bookmark]) -> Option<&'a Bookmark> {
+    bookmarks.iter().find(|bookmark| {
+        bookmark
+            .path
+            .file_name()
+            .is_some_and(|name| glob_match(pattern, &name.to_string_lossy()))
+    })
+}
+
+fn find_fuzzy(root: &Path, query: &str, matching: &Matching) -> Option<PathBuf> {
+    let dir_names: Vec<String> = fs::read_dir(root)
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            if entry.file_type().ok()?.is_dir() {
+                Some(entry.file_name().to_string_lossy().into_owned())
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    matching
+        .best(query, dir_names.iter().map(String::as_str))
+        .map(|(name, _)| root.join(name))
+}
+
+/// The directory below `root` matching `query`: fuzzily for a single name,
+/// otherwise component by component ignoring case or by glob.
+pub fn find_case_insensitive(root: &Path, query: &str, matching: &Matching) -> Option<PathBuf> {
+    if !query.contains('/')
+        && !is_glob(query)
+        && let Some(fuzzy) = find_fuzzy(root, query, matching)
+    {
+        return Some(fuzzy);
+    }
+
+    let mut current = root.to_path_buf();
+
+    for wanted in query.trim_end_matches('/').split('/') {
+        let glob = is_glob(wanted);
+        let wanted = wanted.to_lowercase();
+
+        let mut matched: Option<PathBuf> = None;
+
+        for entry in fs::read_dir(&current).ok()? {
+            let entry = entry.ok()?;
+
+            if !entry.file_type().ok()?.is_dir() {
+                continue;
+            }
+
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            if glob {
+                // Pick the alphabetically first match so results are stable.
+                if glob_match(&wanted, &name) && matched.as_ref().is_none_or(|m| entry.path() < *m)
+                {
+                    matched = Some(entry.path());
+                }
+            } else if name.to_lowercase() == wanted {
+                matched = Some(entry.path());
+                break;
+            }
+        }
+
+        current = matched?;
+    }
+
+    Some(current)
+}
+
+/// The sorted subdirectories of `dir`, following symlinks.
+pub fn list_child_dirs(dir: &Path, include_hidden: bool) -> io::Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+
+    for entry_res in fs::read_dir(dir)? {
+        let entry = entry_res?;
+        let file_type = entry.file_type()?;
+
+        let is_dir = if file_type.is_symlink() {
+            let target = fs::read_link(entry.path())?;
+            let target_abs = if target.is_absolute() {
+                target
+            } else {
+                dir.join(target)
+            };
+            target_abs.is_dir()
+        } else {
+            file_type.is_dir()
+        };
+
+        if !is_dir {
+            continue;
+        }
+
+        if let Some(name) = entry.file_name().to_str() {
+            if !include_hidden && name.starts_with('.') {
+                continue;
+            }
+            out.push(entry.path());
+        }
+    }
+
+    out.sort_unstable();
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frecency_breaks_ties() {
+        let visited = Bookmark {
+            path: PathBuf::from("/a/proj"),
+            visits: Some(5),
+            last_used: Some(now()),
+            ..Default::default()
+        };
+        let bookmarks = [visited, Bookmark::new(PathBuf::from("/b/proj"))];
+
+        assert_eq!(
+            match_bookmarks("proj", &bookmarks, &Matching::default()),
+            Some(PathBuf::from("/a/proj"))
+        );
+    }
+
+    #[test]
+    fn close_matches_only_when_ambiguous() {
+        let matching = Matching::default();
+        let bookmarks = [
+            Bookmark::new(PathBuf::from("/a/proj")),
+            Bookmark::new(PathBuf::from("/b/proj")),
+            Bookmark::new(PathBuf::from("/c/other")),
+        ];
+
+        let close = close_matches("proj", &bookmarks, &matching);
+        assert_eq!(close, bookmarks[..2]);
+
+        assert!(close_matches("proj", &bookmarks[1..], &matching).is_empty());
+    }
+
+    #[test]
+    fn best_with_same_score() {
+        let paths = [
+            "/path/with/many/sub/directories",
+            "/path/with/",
+            "/path/with/many/sub/",
+        ];
+
+        let best = best_bookmark_match("pathwith", paths, |_| 1.0, &Matching::default()).unwrap();
+
+        assert_eq!(best, paths[1]);
+    }
+
+    #[test]
+    fn test_find_case_insensitive_nested() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+
+        let dir_path = root.join("Dir");
+        let subdir_path = dir_path.join("SubDir");
+
+        fs::create_dir_all(&subdir_path).unwrap();
+
+        let found = find_case_insensitive(root, "dIr/sUbDiR", &Matching::default()).unwrap();
+
+        assert_eq!(found, subdir_path);
+    }
+
+    #[test]
+    fn test_find_case_insensitive_fuzzy() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+
+        let dir_1 = root.join("Test_Project");
+        let dir_2 = root.join("other_directory");
+
+        fs::create_dir_all(&dir_1).unwrap();
+        fs::create_dir_all(&dir_2).unwrap();
+
+        let found = find_case_insensitive(root, "tesproj", &Matching::default()).unwrap();
+
+        assert_eq!(found, dir_1);
+    }
+
+    #[test]
+    fn test_find_case_insensitive_not_fuzzy_sub() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+
+        let dir_path = root.join("Dir");
+        let subdir_path = dir_path.join("SubDirectory");
+
+        fs::create_dir_all(&subdir_path).unwrap();
+
+        let found = find_case_insensitive(root, "subdir", &Matching::default());
+
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    fn test_find_case_insensitive_not_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+
+        let dir_path = root.join("Dir");
+
+        fs::create_dir_all(&dir_path).unwrap();
+
+        let file_path = dir_path.join("testfile.txt");
+        fs::write(&file_path, "hello").unwrap();
+
+        let found = find_case_insensitive(root, "testf", &Matching::default());
+
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    fn test_find_case_insensitive_glob() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+
+        fs::create_dir_all(root.join("cli-tools").join("tests")).unwrap();
+        fs::create_dir_all(root.join("client").join("tests")).unwrap();
+        fs::create_dir_all(root.join("my-cli")).unwrap();
+
+        let found = find_case_insensitive(root, "cli-*/tests", &Matching::default()).unwrap();
+        assert_eq!(found, root.join("cli-tools").join("tests"));
+
+        let found = find_case_insensitive(root, "*cli", &Matching::default()).unwrap();
+        assert_eq!(found, root.join("my-cli"));
+    }
+
+    #[test]
+    fn guess_prefers_bookmarks_in_current_repository() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+
+        let repo = root.join("repo");
+        let deep = repo.join("src").join("deep");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(&deep).unwrap();
+
+        let bookmarks = [
+            Bookmark::new(root.join("elsewhere").join("api")),
+            Bookmark::new(repo.join("services").join("api")),
+        ];
+
+        let found = resolve_first("api", &deep, &bookmarks, &Matching::default()).unwrap();
+        assert_eq!(found, repo.join("services").join("api"));
+
+        let found = resolve_first("api", root, &bookmarks, &Matching::default()).unwrap();
+        assert_eq!(found, root.join("elsewhere").join("api"));
+    }
+
+    #[test]
+    fn guess_resolves_names_first() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("work")).unwrap();
+
+        let bookmarks = [
+            Bookmark::new(PathBuf::from("/srv/work")),
+            Bookmark {
+                path: PathBuf::from("/home/me/company/monorepo"),
+                name: Some("work".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let found = resolve_first("work", root, &bookmarks, &Matching::default()).unwrap();
+        assert_eq!(found, PathBuf::from("/home/me/company/monorepo"));
+    }
+
+    #[test]
+    fn guess_resolves_slots_after_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("3")).unwrap();
+
+        let bookmarks = [
+            Bookmark::new(PathBuf::from("/srv/2")),
+            Bookmark {
+                slot: Some(2),
+                ..Bookmark::new(PathBuf::from("/home/me/api"))
+            },
+            Bookmark {
+                slot: Some(3),
+                ..Bookmark::new(PathBuf::from("/home/me/web"))
+            },
+        ];
+
+        let found = resolve_first("2", root, &bookmarks, &Matching::default()).unwrap();
+        assert_eq!(found, PathBuf::from("/home/me/api"));
+        assert_eq!(
+            resolve_first("3", root, &bookmarks, &Matching::default()),
+            Some(root.join("3"))
+        );
+    }
+
+    #[test]
+    fn guess_filters_components_in_order() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let bookmarks = [
+            Bookmark::new(PathBuf::from("/home/me/work/project/src")),
+            Bookmark::new(PathBuf::from("/home/me/play/project/docs")),
+            Bookmark::new(PathBuf::from("/home/me/src/project")),
+        ];
+        let guess = |query: &[&str]| {
+            let query: Vec<_> = query.iter().map(|q| q.to_string()).collect();
+            guess(&query, root, &bookmarks, 0, &Matching::default())
+        };
+
+        assert_eq!(
+            guess(&["proj", "src"]),
+            PathBuf::from("/home/me/work/project/src")
+        );
+        assert_eq!(
+            guess(&["proj docs"]),
+            PathBuf::from("/home/me/play/project/docs")
+        );
+        assert_eq!(guess(&["src proj"]), PathBuf::from("/home/me/src/project"));
+    }
+
+    #[test]
+    fn guess_searches_below_bookmarks() {
+        let temp = tempfile::tempdir().unwrap();
+        let monorepo = temp.path().join("monorepo");
+        let billing = monorepo.join("services").join("billing");
+        fs::create_dir_all(&billing).unwrap();
+        let bookmarks = [Bookmark::new(monorepo)];
+
+        assert_eq!(
+            search_below("billing", &bookmarks, 1, &Matching::default()),
+            None
+        );
+        assert_eq!(
+            search_below("billing", &bookmarks, 2, &Matching::default()),
+            Some(billing)
+        );
+    }
+
+    #[test]
+    fn guess_finds_ancestors_before_bookmarks() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("Repo");
+        let deep = repo.join("src").join("deep");
+        fs::create_dir_all(&deep).unwrap();
+        let bookmarks = [Bookmark::new(PathBuf::from("/home/me/repo"))];
+
+        assert_eq!(
+            resolve_first("repo", &deep, &bookmarks, &Matching::default()),
+            Some(repo.clone())
+        );
+        assert_eq!(
+            resolve_first("sr", &deep, &bookmarks, &Matching::default()),
+            Some(repo.join("src"))
+        );
+    }
+
+    #[test]
+    fn guess_appends_subpath_to_bookmark() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let api = root.join("services").join("api");
+        fs::create_dir_all(api.join("migrations")).unwrap();
+        let bookmarks = [Bookmark::new(api.clone())];
+
+        assert_eq!(
+            resolve_first("api/migrations", root, &bookmarks, &Matching::default()),
+            Some(api.join("migrations"))
+        );
+        assert_eq!(
+            resolve_subpath("api/missing", &bookmarks, &Matching::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn guess_falls_back_to_repository_root_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(repo.join("docs")).unwrap();
+        fs::create_dir_all(repo.join("src").join("deep")).unwrap();
+
+        let found = resolve_first(
+            "docs",
+            &repo.join("src").join("deep"),
+            &[],
+            &Matching::default(),
+        )
+        .unwrap();
+        assert_eq!(found, repo.join("docs"));
+    }
+
+    #[test]
+    fn shortest_path_wins_when_scores_equal() {
+        let paths = [
+            "/path/with/many/sub/directories",
+            "/path/with/",
+            "/path/with/many/sub/",
+        ];
+
+        let best = best_bookmark_match("pathwith", paths, |_| 1.0, &Matching::default()).unwrap();
+
+        assert_eq!(best, "/path/with/");
+    }
+
+    #[test]
+    fn nested_query_does_not_use_root_fuzzy_match() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+
+        fs::create_dir_all(root.join("Project")).unwrap();
+        fs::create_dir_all(root.join("Dir").join("SubDir")).unwrap();
+
+        let found = find_case_insensitive(root, "dir/subdir", &Matching::default()).unwrap();
+
+        assert_eq!(found, root.join("Dir").join("SubDir"));
+    }
+
+    #[test]
+    fn test_find_case_insensitive_unicode() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+
+        let dir = root.join("Risengrød");
+
+        fs::create_dir_all(&dir).unwrap();
+
+        let found = find_case_insensitive(root, "risengrød", &Matching::default()).unwrap();
+
+        assert_eq!(found, dir);
+    }
+
+    #[test]
+    fn test_find_case_insensitive_unicode_nested() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+
+        let subdir = root.join("Rød").join("Grød");
+
+        fs::create_dir_all(&subdir).unwrap();
+
+        let found = find_case_insensitive(root, "rød/grød", &Matching::default()).unwrap();
+
+        assert_eq!(found, subdir);
+    }
+}
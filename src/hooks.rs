@@ -0,0 +1,75 @@
+//! Commands from the `[hooks]` table of the config, run after a bookmark is
+//! saved, removed or jumped to. A failing hook is only logged, so it never
+//! fails the command that ran it.
+
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use crate::command::{from_template, run};
+
+/// Each hook is a command with `{}` standing for the path, appended when
+/// there is none. Their output goes to stderr, leaving stdout to pathmarks.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Hooks {
+    pub save: Option<String>,
+    pub remove: Option<String>,
+    /// Run when `guess` or `pick` print a path to jump to.
+    pub jump: Option<String>,
+}
+
+impl Hooks {
+    pub fn saved(&self, path: &Path) {
+        run_hook("save", self.save.as_deref(), path);
+    }
+
+    pub fn removed(&self, path: &Path) {
+        run_hook("remove", self.remove.as_deref(), path);
+    }
+
+    pub fn jumped(&self, path: &Path) {
+        run_hook("jump", self.jump.as_deref(), path);
+    }
+}
+
+/// Runs `template` for `path`, with the event in `PATHMARKS_EVENT` for
+/// scripts used for several hooks.
+fn run_hook(event: &str, template: Option<&str>, path: &Path) {
+    let Some(template) = template else {
+        return;
+    };
+
+    debug!("Running the {event} hook for {}", path.display());
+    let result = from_template(template, path).and_then(|mut command| {
+        command.env("PATHMARKS_EVENT", event).stdout(io::stderr());
+        run(command)
+    });
+    if let Err(err) = result {
+        warn!("The {event} hook failed: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_hooks_without_failing() {
+        let temp = tempfile::tempdir().unwrap();
+        let saved = temp.path().join("saved");
+        let hooks = Hooks {
+            save: Some("touch {}".to_string()),
+            remove: Some("false".to_string()),
+            jump: Some("pathmarks-no-such-program".to_string()),
+        };
+
+        hooks.saved(&saved);
+        hooks.removed(&saved);
+        hooks.jumped(&saved);
+
+        assert!(saved.exists());
+    }
+}
@@ -0,0 +1,141 @@
+//! Imports directories from other jump tools, and exports of pathmarks.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::command::{filter, from_line};
+use crate::error::{AppError, AppResult};
+use crate::export::parse_export;
+use crate::path_cmp::simplify;
+use crate::store::Bookmark;
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum ImportSource {
+    Zoxide,
+    Autojump,
+    /// z.sh
+    Z,
+    /// A file written by `pathmarks export`
+    File,
+}
+
+/// Bookmarks to import: the existing directories `source` scores at least
+/// `min_score`, canonicalized, or every entry of the export `file`.
+pub fn imported(
+    source: ImportSource,
+    file: Option<&Path>,
+    min_score: f64,
+) -> AppResult<Vec<Bookmark>> {
+    let scored = match (source, file) {
+        (ImportSource::File, Some(file)) => return parse_export(&fs::read_to_string(file)?),
+        (ImportSource::File, None) => return Err(AppError::NotFound("export file".to_string())),
+        _ => scored_dirs(source)?,
+    };
+
+    Ok(scored
+        .into_iter()
+        .filter(|(score, _)| *score >= min_score)
+        .filter_map(|(_, path)| Some(Bookmark::new(simplify(path.canonicalize().ok()?))))
+        .collect())
+}
+
+/// Directories known to a jump tool, with the score it gives them.
+fn scored_dirs(source: ImportSource) -> AppResult<Vec<(f64, PathBuf)>> {
+    match source {
+        ImportSource::Zoxide => {
+            let output = filter(from_line("zoxide query --list --score")?, &[])?;
+            Ok(parse_scored(&String::from_utf8_lossy(&output), ' '))
+        }
+        ImportSource::Autojump => {
+            let content = fs::read_to_string(autojump_file()?)?;
+            Ok(parse_scored(&content, '\t'))
+        }
+        ImportSource::Z => Ok(parse_z(&fs::read_to_string(z_file()?)?)),
+        ImportSource::File => Ok(Vec::new()),
+    }
+}
+
+/// Parses `<score><separator><path>` lines, skipping malformed ones.
+fn parse_scored(output: &str, separator: char) -> Vec<(f64, PathBuf)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (score, path) = line.trim_start().split_once(separator)?;
+            Some((score.parse().ok()?, PathBuf::from(path)))
+        })
+        .collect()
+}
+
+/// Parses the `<path>|<rank>|<time>` lines of z.sh.
+fn parse_z(content: &str) -> Vec<(f64, PathBuf)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.rsplitn(3, '|');
+            let _time = fields.next()?;
+            let rank = fields.next()?.parse().ok()?;
+            Some((rank, PathBuf::from(fields.next()?)))
+        })
+        .collect()
+}
+
+fn autojump_file() -> AppResult<PathBuf> {
+    let dir = if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|home| home.join("Library"))
+    } else {
+        dirs::data_dir()
+    };
+
+    Ok(dir
+        .ok_or(AppError::DataDirectoryNotFound)?
+        .join("autojump")
+        .join("autojump.txt"))
+}
+
+fn z_file() -> AppResult<PathBuf> {
+    match env::var_os("_Z_DATA") {
+        Some(file) => Ok(PathBuf::from(file)),
+        None => Ok(dirs::home_dir()
+            .ok_or(AppError::HomeDirectoryNotFound)?
+            .join(".z")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_zoxide_scores() {
+        let output = "  48.0 /home/me/src/pathmarks\n   2.5 /tmp/with space\nbroken\n";
+
+        assert_eq!(
+            parse_scored(output, ' '),
+            [
+                (48.0, PathBuf::from("/home/me/src/pathmarks")),
+                (2.5, PathBuf::from("/tmp/with space")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_autojump_and_z() {
+        let autojump = "22.4\t/home/me/src\n";
+        assert_eq!(
+            parse_scored(autojump, '\t'),
+            [(22.4, PathBuf::from("/home/me/src"))]
+        );
+
+        let z = "/home/me/a|b|12|1700000000\n/home/me/docs|3|1700000001\n";
+        assert_eq!(
+            parse_z(z),
+            [
+                (12.0, PathBuf::from("/home/me/a|b")),
+                (3.0, PathBuf::from("/home/me/docs")),
+            ]
+        );
+    }
+}
@@ -1,13 +1,62 @@
-use std::path::PathBuf;
+use std::io;
+
+use crate::store::Bookmark;
+use crate::theme::{Style, theme};
+
+/// Marks pinned bookmarks in the picker and `list --pretty`.
+pub const PIN: &str = "📌";
+/// Marks bookmarked files in the picker and `list --pretty`.
+pub const FILE: &str = "📄";
 
 pub struct IndexPathRenderer<'a> {
-    items: &'a [PathBuf],
+    items: &'a [Bookmark],
+    styles: Vec<&'static Style>,
+    files: Vec<bool>,
 }
 
 impl<'a> IndexPathRenderer<'a> {
-    pub fn new(items: &'a [PathBuf]) -> Self {
-        Self { items }
+    pub fn new(items: &'a [Bookmark]) -> Self {
+        let theme = theme(&io::stderr());
+        let styles = items
+            .iter()
+            .map(|b| theme.entry(&b.path, b.pinned, false, false))
+            .collect();
+        let files = items.iter().map(|b| b.path.is_file()).collect();
+        Self {
+            items,
+            styles,
+            files,
+        }
+    }
+
+    /// How a bookmark is shown, without styling.
+    pub fn label(&self, idx: usize) -> String {
+        label(&self.items[idx], self.files[idx])
+    }
+}
+
+/// How a bookmark is shown in the pickers, without styling. Bookmarked
+/// files without an icon of their own get [`FILE`].
+pub fn label(bookmark: &Bookmark, file: bool) -> String {
+    let mut path = bookmark.path.to_string_lossy().into_owned();
+    if let Some(icon) = &bookmark.icon {
+        path = format!("{icon} {path}");
+    } else if file {
+        path = format!("{FILE} {path}");
+    }
+    if let Some(name) = &bookmark.name {
+        path = format!("{path} ({name})");
+    }
+    if let Some(note) = &bookmark.note {
+        path = format!("{path} — {note}");
+    }
+    if bookmark.pinned {
+        path = format!("{PIN} {path}");
+    }
+    if let Some(slot) = bookmark.slot {
+        path = format!("[{slot}] {path}");
     }
+    path
 }
 
 impl<'a> nucleo_picker::Render<usize> for IndexPathRenderer<'a> {
@@ -17,7 +66,6 @@ impl<'a> nucleo_picker::Render<usize> for IndexPathRenderer<'a> {
         usize: 'b;
 
     fn render<'b>(&self, idx: &'b usize) -> Self::Str<'b> {
-        let path = &self.items[*idx];
-        path.to_string_lossy().to_string()
+        self.styles[*idx].paint(&self.label(*idx))
     }
 }
@@ -1,36 +1,152 @@
-use clap::ValueEnum;
+use clap::{Args, ValueEnum};
+
+use crate::error::{AppError, AppResult};
+use crate::store::Bookmark;
 
 #[derive(Copy, Clone, Debug, ValueEnum)]
 pub enum Shell {
     Fish,
+    /// Windows cmd.exe, as a batch file to put on `PATH`
+    Cmd,
+    /// Nushell, as a script to save and `source`
+    Nu,
     // Zsh,
     // Bash,
-    // Nu,
 }
 
-pub fn init(shell: Shell, command: Option<String>) -> String {
+/// Prompts that show `pathmarks status` through their own config.
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum Prompt {
+    /// A module for `starship.toml`
+    Starship,
+}
+
+#[derive(Args, Clone, Debug, Default)]
+pub struct InitOptions {
+    /// Also define an abbreviation for every named bookmark
+    #[arg(long)]
+    pub abbr: bool,
+    /// Make a failing `cd` retry with `pathmarks guess`
+    #[arg(long)]
+    pub cd: bool,
+    /// Record directory changes with `pathmarks visit`, for frecency and
+    /// `pathmarks recent`
+    #[arg(long)]
+    pub hook: bool,
+    /// Only define the helpers named with the alias options below
+    #[arg(long)]
+    pub no_aliases: bool,
+    /// Name of the helper saving the current directory, `<COMMAND>s` by default
+    #[arg(long, value_name = "NAME")]
+    pub save_alias: Option<String>,
+    /// Name of the helper removing bookmarks, `<COMMAND>d` by default
+    #[arg(long, value_name = "NAME")]
+    pub remove_alias: Option<String>,
+    /// Name of the helper picking until escape, `<COMMAND>i` by default
+    #[arg(long, value_name = "NAME")]
+    pub loop_alias: Option<String>,
+    /// Bind a key inserting a picked path at the cursor, e.g. `\ep` for alt-p
+    #[arg(long, value_name = "KEY")]
+    pub insert_key: Option<String>,
+    /// Show `pathmarks status` in the right prompt
+    #[arg(long)]
+    pub prompt: bool,
+}
+
+impl InitOptions {
+    /// The command line flags giving these options.
+    pub fn flags(&self) -> Vec<String> {
+        let switches = [
+            (self.abbr, "--abbr"),
+            (self.cd, "--cd"),
+            (self.hook, "--hook"),
+            (self.no_aliases, "--no-aliases"),
+            (self.prompt, "--prompt"),
+        ];
+        let names = [
+            (&self.save_alias, "--save-alias"),
+            (&self.remove_alias, "--remove-alias"),
+            (&self.loop_alias, "--loop-alias"),
+            (&self.insert_key, "--insert-key"),
+        ];
+
+        let mut flags: Vec<String> = switches
+            .into_iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, flag)| flag.to_string())
+            .collect();
+        for (name, flag) in names {
+            if let Some(name) = name {
+                flags.extend([flag.to_string(), name.clone()]);
+            }
+        }
+        flags
+    }
+
+    /// The name of a helper, unless it is left out.
+    fn alias(&self, name: &Option<String>, command: &str, suffix: &str) -> Option<String> {
+        name.clone()
+            .or_else(|| (!self.no_aliases).then(|| format!("{command}{suffix}")))
+    }
+}
+
+pub fn init(shell: Shell, command: Option<String>, options: &InitOptions) -> String {
     let command = command.unwrap_or_else(|| "t".to_string());
     match shell {
-        Shell::Fish => fish_init(&command),
+        Shell::Fish => fish_init(&command, options),
+        Shell::Cmd => cmd_init(&command),
+        Shell::Nu => nu_init(&command, options, ""),
         // Shell::Zsh => zsh_init(&command),
         // Shell::Bash => bash_init(&command),
-        // Shell::Nu => nu_init(&command),
     }
 }
 
-fn fish_init(command: &str) -> String {
-    format!(
+fn fish_init(command: &str, options: &InitOptions) -> String {
+    let mut out = format!(
         r#"function {command}
+    if test (count $argv) -eq 1; and string match -qr '^[0-9]+$' -- $argv[1]; and not test -d $argv[1]
+        set -l dest (pathmarks goto $argv[1]); or return
+        __pathmarks_go "$dest"
+        return
+    end
+
     if test (count $argv) -gt 0
-        cd (pathmarks guess $argv)
+        set -l dest (pathmarks guess $argv)
+        set -l code $status
+        if test $code -eq 3
+            set dest (pathmarks pick --query "$argv" --select-1)
+            set code $status
+        end
+        test $code -eq 0; or return $code
+        __pathmarks_go "$dest"
         return
     end
 
-    set p (pathmarks pick)
-    test -n "$p"; and cd "$p"
+    set -l dest (pathmarks pick); or return
+    test -n "$dest"; and __pathmarks_go "$dest"
+end
+
+function __pathmarks_go
+    if string match -q 'ssh://*' -- $argv[1]
+        set -l rest (string replace 'ssh://' '' -- $argv[1])
+        set -l host (string split -m1 / -- $rest)[1]
+        set -l dir /(string split -m1 / -- $rest)[2]
+        ssh -t $host "cd '"(string replace -a "'" "'\\''" -- $dir)"' && exec \$SHELL -l"
+    else if test -f $argv[1]
+        pathmarks open $argv[1]
+    else
+        cd $argv[1]
+    end
 end
 
-function {command}i
+complete --keep-order -c {command} -d "Pathmarks" --wraps cd -a "(pathmarks complete (commandline -ct))"
+"#
+    );
+
+    if let Some(name) = options.alias(&options.loop_alias, command, "i") {
+        out.push_str(&format!(
+            r#"
+function {name}
     while true
         set -l dest (pathmarks pick)
         set -l code $status
@@ -46,10 +162,357 @@ function {command}i
         end
     end
 end
+"#
+        ));
+    }
+    if let Some(name) = options.alias(&options.save_alias, command, "s") {
+        out.push_str(&format!("alias {name} \"pathmarks save\"\n"));
+    }
+    if let Some(name) = options.alias(&options.remove_alias, command, "d") {
+        out.push_str(&format!("alias {name} \"pathmarks remove\"\n"));
+    }
+
+    out
+}
+
+/// The nushell helpers, each definition starting with `export` for a module.
+/// Unlike fish, nushell only completes from functions, so the remove helper
+/// is one too.
+fn nu_init(command: &str, options: &InitOptions, export: &str) -> String {
+    let mut out = format!(
+        r#"def __pathmarks_complete [context: string] {{
+    ^pathmarks complete ($context | split row " " | last) | lines
+}}
+
+def __pathmarks_list_absolute [] {{
+    ^pathmarks list --absolute | lines
+}}
 
-alias {command}s "pathmarks save"
-alias {command}d "pathmarks remove"
-complete --keep-order -c {command} -d "Pathmarks" --wraps cd -a "(pathmarks list)"
+def --env __pathmarks_go [dest: string] {{
+    if ($dest | path type) == "file" {{
+        ^pathmarks open $dest
+    }} else {{
+        cd $dest
+    }}
+}}
+
+def --env __pathmarks_pick [args: list<string>] {{
+    let dest = try {{ ^pathmarks pick ...$args | str trim }} catch {{ "" }}
+    if ($dest | is-not-empty) {{
+        __pathmarks_go $dest
+    }}
+}}
+
+{export}def --env {command} [...query: string@__pathmarks_complete] {{
+    if ($query | is-empty) {{
+        __pathmarks_pick []
+        return
+    }}
+
+    if ($query | length) == 1 and $query.0 =~ '^[0-9]+$' and not ($query.0 | path exists) {{
+        let dest = try {{ ^pathmarks goto $query.0 | str trim }} catch {{ "" }}
+        if ($dest | is-not-empty) {{
+            cd $dest
+        }}
+        return
+    }}
+
+    let guess = ^pathmarks guess ...$query | complete
+    match $guess.exit_code {{
+        0 => {{ __pathmarks_go ($guess.stdout | str trim) }}
+        3 => {{ __pathmarks_pick ["--query" ($query | str join " ") "--select-1"] }}
+        _ => {{ print --stderr --no-newline $guess.stderr }}
+    }}
+}}
+"#
+    );
+
+    if let Some(name) = options.alias(&options.loop_alias, command, "i") {
+        out.push_str(&format!(
+            r#"
+{export}def --env {name} [] {{
+    loop {{
+        let dest = try {{ ^pathmarks pick | str trim }} catch {{ "" }}
+        if ($dest | is-empty) or ($dest | path type) != "dir" {{
+            break
+        }}
+        cd $dest
+    }}
+}}
+"#
+        ));
+    }
+    if let Some(name) = options.alias(&options.save_alias, command, "s") {
+        out.push_str(&format!("\n{export}alias {name} = ^pathmarks save\n"));
+    }
+    if let Some(name) = options.alias(&options.remove_alias, command, "d") {
+        out.push_str(&format!(
+            r#"
+{export}def {name} [path?: string@__pathmarks_list_absolute] {{
+    ^pathmarks remove ...([$path] | compact)
+}}
+"#
+        ));
+    }
+
+    out
+}
+
+/// `pathmarks nu-module`, the nushell helpers as a module for `overlay use`,
+/// with the directory hook in its `export-env` block.
+pub fn nu_module(command: Option<String>, options: &InitOptions) -> AppResult<String> {
+    let unsupported_options = [
+        (options.abbr, "--abbr"),
+        (options.cd, "--cd"),
+        (options.insert_key.is_some(), "--insert-key"),
+        (options.prompt, "--prompt"),
+    ];
+    if let Some((_, option)) = unsupported_options.iter().find(|(on, _)| *on) {
+        return Err(unsupported(option, Shell::Nu));
+    }
+
+    let command = command.unwrap_or_else(|| "t".to_string());
+    let mut out = String::from("# Load with `overlay use`, e.g. `overlay use pathmarks.nu`\n\n");
+    if options.hook {
+        let hook: String = NU_VISIT_HOOK
+            .lines()
+            .map(|line| format!("    {line}\n"))
+            .collect();
+        out.push_str(&format!("export-env {{\n{hook}}}\n\n"));
+    }
+    out.push_str(&nu_init(&command, options, "export "));
+    Ok(out)
+}
+
+/// Batch files run in the calling cmd.exe, so `cd /d` changes its directory.
+fn cmd_init(command: &str) -> String {
+    format!(
+        r#"@echo off
+rem Save as {command}.bat in a directory on PATH: pathmarks init cmd > {command}.bat
+if "%~1"=="" (
+    for /f "delims=" %%i in ('pathmarks pick') do cd /d "%%i"
+) else (
+    for /f "delims=" %%i in ('pathmarks guess %*') do cd /d "%%i"
+)
 "#
     )
+    .replace('\n', "\r\n")
+}
+
+/// The config showing `pathmarks status` in `prompt`.
+pub fn prompt_config(prompt: Prompt) -> String {
+    match prompt {
+        Prompt::Starship => STARSHIP.to_string(),
+    }
+}
+
+const STARSHIP: &str = r#"[custom.pathmarks]
+command = "pathmarks status"
+when = true
+format = "[$output]($style) "
+style = "yellow"
+"#;
+
+const NU_VISIT_HOOK: &str = r#"$env.config = (
+    $env.config?
+    | default {}
+    | upsert hooks { default {} }
+    | upsert hooks.env_change { default {} }
+    | upsert hooks.env_change.PWD { default [] }
+)
+$env.config.hooks.env_change.PWD = (
+    $env.config.hooks.env_change.PWD | append {|_, dir| ^pathmarks visit $dir }
+)
+"#;
+
+/// Records every directory change with `pathmarks visit`, for frecency and
+/// `pathmarks recent`.
+pub fn visit_hook(shell: Shell) -> String {
+    match shell {
+        Shell::Fish => r#"function __pathmarks_visit --on-variable PWD
+    pathmarks visit "$PWD"
+end
+"#
+        .to_string(),
+        Shell::Nu => NU_VISIT_HOOK.to_string(),
+        // cmd.exe has no hook on directory changes.
+        Shell::Cmd => String::new(),
+    }
+}
+
+/// Shows `pathmarks status` before the right prompt that is there already.
+pub fn prompt(shell: Shell) -> AppResult<String> {
+    match shell {
+        Shell::Fish => Ok(
+            r#"if functions -q fish_right_prompt; and not functions -q __pathmarks_right_prompt
+    functions --copy fish_right_prompt __pathmarks_right_prompt
+end
+
+function fish_right_prompt
+    set -l mark (pathmarks status)
+    test -n "$mark"; and echo -n "$mark "
+    functions -q __pathmarks_right_prompt; and __pathmarks_right_prompt
+end
+"#
+            .to_string(),
+        ),
+        Shell::Cmd | Shell::Nu => Err(unsupported("--prompt", shell)),
+    }
+}
+
+pub fn unsupported(option: &'static str, shell: Shell) -> AppError {
+    let shell = match shell {
+        Shell::Fish => "fish",
+        Shell::Cmd => "cmd",
+        Shell::Nu => "nu",
+    };
+    AppError::ShellUnsupported { option, shell }
+}
+
+/// Binds `key` to pick a bookmark and insert its path into the command line
+/// at the cursor, instead of changing directory.
+pub fn insert_widget(shell: Shell, key: &str) -> AppResult<String> {
+    match shell {
+        Shell::Fish => Ok(format!(
+            r#"function __pathmarks_insert
+    set -l p (pathmarks pick)
+    test -n "$p"; and commandline --insert -- (string escape -- $p)
+    commandline -f repaint
+end
+
+bind {key} __pathmarks_insert
+bind -M insert {key} __pathmarks_insert
+"#
+        )),
+        Shell::Cmd | Shell::Nu => Err(unsupported("--insert-key", shell)),
+    }
+}
+
+/// Wraps `cd` to retry with `pathmarks guess` when the directory does not exist.
+pub fn cd_fallback(shell: Shell) -> AppResult<String> {
+    match shell {
+        Shell::Fish => Ok(fish_cd_fallback()),
+        Shell::Cmd | Shell::Nu => Err(unsupported("--cd", shell)),
+    }
+}
+
+fn fish_cd_fallback() -> String {
+    r#"if not functions -q __pathmarks_cd
+    functions --copy cd __pathmarks_cd
+end
+
+function cd --wraps cd
+    __pathmarks_cd $argv 2>/dev/null; and return
+
+    if test (count $argv) -eq 1
+        set -l dest (pathmarks guess $argv[1])
+        if test -d "$dest"
+            __pathmarks_cd "$dest"
+            return
+        end
+    end
+
+    __pathmarks_cd $argv
+end
+"#
+    .to_string()
+}
+
+/// Defines a shell abbreviation jumping to each named bookmark.
+pub fn abbreviations(shell: Shell, bookmarks: &[Bookmark]) -> AppResult<String> {
+    match shell {
+        Shell::Fish => Ok(fish_abbreviations(bookmarks)),
+        Shell::Cmd | Shell::Nu => Err(unsupported("--abbr", shell)),
+    }
+}
+
+fn fish_abbreviations(bookmarks: &[Bookmark]) -> String {
+    bookmarks
+        .iter()
+        .filter_map(|bookmark| {
+            let name = bookmark.name.as_deref()?;
+            if name.contains(char::is_whitespace) {
+                return None;
+            }
+
+            let expansion = format!("cd {}", fish_quote(&bookmark.path.to_string_lossy()));
+            Some(format!(
+                "abbr --add -- {} {}\n",
+                fish_quote(name),
+                fish_quote(&expansion)
+            ))
+        })
+        .collect()
+}
+
+pub fn fish_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn fish_abbreviations_for_named_bookmarks() {
+        let bookmarks = [
+            Bookmark {
+                path: PathBuf::from("/src/mono repo"),
+                name: Some("jm".to_string()),
+                ..Default::default()
+            },
+            Bookmark::new(PathBuf::from("/unnamed")),
+            Bookmark {
+                path: PathBuf::from("/src/it's"),
+                name: Some("it".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let expected = [
+            r"abbr --add -- 'jm' 'cd \'/src/mono repo\''",
+            r"abbr --add -- 'it' 'cd \'/src/it\\\'s\''",
+        ];
+
+        assert_eq!(
+            abbreviations(Shell::Fish, &bookmarks).unwrap(),
+            expected.join("\n") + "\n"
+        );
+    }
+
+    #[test]
+    fn fish_connects_to_remote_bookmarks() {
+        let init = init(Shell::Fish, None, &InitOptions::default());
+        assert!(init.contains("if string match -q 'ssh://*' -- $argv[1]"));
+        assert!(init.contains(r#"ssh -t $host "cd '"(string replace -a"#));
+    }
+
+    #[test]
+    fn nu_module_exports_the_helpers() {
+        let options = InitOptions {
+            remove_alias: Some("forget".to_string()),
+            hook: true,
+            ..Default::default()
+        };
+
+        let init = init(Shell::Nu, None, &options);
+        assert!(init.contains("def --env t [...query: string@__pathmarks_complete]"));
+        assert!(init.contains("def forget [path?: string@__pathmarks_list_absolute]"));
+        assert!(init.contains("alias ts = ^pathmarks save"));
+
+        let module = nu_module(Some("j".to_string()), &options).unwrap();
+        assert!(module.starts_with("# Load with `overlay use`"));
+        assert!(module.contains("export-env {\n    $env.config = ("));
+        assert!(module.contains("export def --env j [...query"));
+        assert!(module.contains("export def forget ["));
+        assert!(module.contains("\ndef __pathmarks_complete [context: string]"));
+
+        let prompt = InitOptions {
+            prompt: true,
+            ..Default::default()
+        };
+        assert!(nu_module(None, &prompt).is_err());
+    }
 }
@@ -0,0 +1,158 @@
+//! Adds the `pathmarks init` line to the shell's rc file, and removes it
+//! again. The line is tagged with [`MARKER`] so it can be found later.
+
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+use crate::init::{InitOptions, Shell, fish_quote, unsupported};
+use crate::store::write_atomic;
+
+const MARKER: &str = "# added by pathmarks init --install";
+
+pub fn rc_file(shell: Shell) -> AppResult<PathBuf> {
+    match shell {
+        Shell::Fish => {
+            let config = env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+                .ok_or(AppError::HomeDirectoryNotFound)?;
+            Ok(config.join("fish").join("config.fish"))
+        }
+        Shell::Cmd | Shell::Nu => Err(unsupported("--install", shell)),
+    }
+}
+
+/// The line sourcing `pathmarks init` with the same arguments.
+pub fn init_line(shell: Shell, command: Option<&str>, options: &InitOptions) -> String {
+    let mut args = match shell {
+        Shell::Fish => vec!["fish".to_string()],
+        Shell::Cmd => vec!["cmd".to_string()],
+        Shell::Nu => vec!["nu".to_string()],
+    };
+    args.extend(command.map(String::from));
+    args.extend(options.flags());
+    let args: Vec<_> = args.iter().map(|arg| shell_word(arg)).collect();
+
+    format!("pathmarks init {} | source {MARKER}", args.join(" "))
+}
+
+/// Quotes `arg` unless the shell reads it as is.
+fn shell_word(arg: &str) -> String {
+    if arg
+        .chars()
+        .all(|c| c.is_alphanumeric() || "-_./".contains(c))
+    {
+        arg.to_string()
+    } else {
+        fish_quote(arg)
+    }
+}
+
+pub fn install(rc: &Path, line: &str) -> AppResult<()> {
+    let (file, content) = read_rc(rc)?.unwrap_or_else(|| (rc.to_path_buf(), String::new()));
+    write_atomic(with_line(&content, line).as_bytes(), &file)
+}
+
+pub fn uninstall(rc: &Path) -> AppResult<()> {
+    let Some((file, content)) = read_rc(rc)? else {
+        return Ok(());
+    };
+    write_atomic(without_line(&content).as_bytes(), &file)
+}
+
+/// The file `rc` links to, so a dotfile managed by a symlink stays one, and
+/// its content. Nothing when it does not exist yet; any other failure is an
+/// error rather than an empty file that would replace the config.
+fn read_rc(rc: &Path) -> io::Result<Option<(PathBuf, String)>> {
+    match fs::canonicalize(rc) {
+        Ok(file) => {
+            let content = fs::read_to_string(&file)?;
+            Ok(Some((file, content)))
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Replaces a previously installed line, or appends one.
+fn with_line(content: &str, line: &str) -> String {
+    if content.lines().any(|l| l.ends_with(MARKER)) {
+        let lines: Vec<_> = content
+            .lines()
+            .map(|l| if l.ends_with(MARKER) { line } else { l })
+            .collect();
+        return lines.join("\n") + "\n";
+    }
+
+    let mut content = content.to_string();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content + line + "\n"
+}
+
+fn without_line(content: &str) -> String {
+    content
+        .lines()
+        .filter(|l| !l.ends_with(MARKER))
+        .map(|l| format!("{l}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn install_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let rc = dir.path().join("fish").join("config.fish");
+        fs::create_dir_all(rc.parent().unwrap()).unwrap();
+        fs::write(&rc, "set -x EDITOR hx").unwrap();
+
+        let options = InitOptions {
+            abbr: true,
+            cd: true,
+            insert_key: Some(r"\ep".to_string()),
+            ..Default::default()
+        };
+        install(&rc, &init_line(Shell::Fish, None, &InitOptions::default())).unwrap();
+        install(&rc, &init_line(Shell::Fish, Some("j"), &options)).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&rc).unwrap(),
+            format!(
+                "set -x EDITOR hx\npathmarks init fish j --abbr --cd --insert-key '\\\\ep' | source {MARKER}\n"
+            )
+        );
+
+        uninstall(&rc).unwrap();
+        assert_eq!(fs::read_to_string(&rc).unwrap(), "set -x EDITOR hx\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_keeps_linked_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("dotfiles").join("config.fish");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, "set -x EDITOR hx\n").unwrap();
+        let rc = dir.path().join("config.fish");
+        std::os::unix::fs::symlink(&target, &rc).unwrap();
+
+        install(&rc, "line").unwrap();
+        assert!(fs::symlink_metadata(&rc).unwrap().is_symlink());
+        assert_eq!(
+            fs::read_to_string(&target).unwrap(),
+            "set -x EDITOR hx\nline\n"
+        );
+
+        // Not UTF-8, so it is kept as it is.
+        fs::write(&target, b"\xff\n").unwrap();
+        assert!(install(&rc, "line").is_err());
+        assert_eq!(fs::read(&target).unwrap(), b"\xff\n");
+    }
+}
@@ -0,0 +1,78 @@
+//! Remembers the last entry picked in each directory so the picker can
+//! start with it highlighted.
+//!
+//! Stored next to the bookmarks file as `<directory>\t<picked entry>` lines,
+//! most recent first.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppResult;
+use crate::store::write_atomic;
+
+const MAX_CONTEXTS: usize = 64;
+
+pub fn last_pick_file(bookmarks_file: &Path) -> PathBuf {
+    bookmarks_file.with_file_name("last_pick.txt")
+}
+
+pub fn read_last_pick(file: &Path, context: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(file).ok()?;
+
+    content.lines().find_map(|line| {
+        let (dir, picked) = line.split_once('\t')?;
+        (Path::new(dir) == context).then(|| PathBuf::from(picked))
+    })
+}
+
+pub fn write_last_pick(file: &Path, context: &Path, picked: &Path) -> AppResult<()> {
+    let content = fs::read_to_string(file).unwrap_or_default();
+
+    let others = content.lines().filter(|line| {
+        line.split_once('\t')
+            .is_some_and(|(dir, _)| Path::new(dir) != context)
+    });
+
+    let current = format!("{}\t{}", context.display(), picked.display());
+    let lines: Vec<_> = std::iter::once(current.as_str())
+        .chain(others)
+        .take(MAX_CONTEXTS)
+        .collect();
+
+    write_atomic((lines.join("\n") + "\n").as_bytes(), file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remembers_per_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("last_pick.txt");
+
+        write_last_pick(&file, Path::new("/a"), Path::new("..")).unwrap();
+        write_last_pick(&file, Path::new("/b"), Path::new("/a")).unwrap();
+        write_last_pick(&file, Path::new("/a"), Path::new("sub")).unwrap();
+
+        assert_eq!(
+            read_last_pick(&file, Path::new("/a")),
+            Some(PathBuf::from("sub"))
+        );
+        assert_eq!(
+            read_last_pick(&file, Path::new("/b")),
+            Some(PathBuf::from("/a"))
+        );
+        assert_eq!(read_last_pick(&file, Path::new("/c")), None);
+    }
+
+    #[test]
+    fn missing_file_has_no_last_pick() {
+        let temp = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            read_last_pick(&temp.path().join("missing"), Path::new("/a")),
+            None
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Path bookmarks for your shell.
+//!
+//! Besides the `pathmarks` binary, the crate resolves queries the way
+//! `pathmarks guess` does, for editor plugins and other tools that would
+//! rather not shell out:
+//!
+//! ```no_run
+//! use std::env;
+//!
+//! use pathmarks::guess::guess;
+//! use pathmarks::matching::Matching;
+//! use pathmarks::store::{Store, TextStore};
+//!
+//! let store = TextStore {
+//!     file: "/home/me/.local/share/pathmarks/bookmarks.txt".into(),
+//!     encryption: None,
+//! };
+//! let bookmarks = store.read()?;
+//! let query = ["api".to_string(), "src".to_string()];
+//! let dir = guess(&query, &env::current_dir()?, &bookmarks, 1, &Matching::default());
+//! # Ok::<(), pathmarks::error::AppError>(())
+//! ```
+//!
+//! - [`store`] reads and writes bookmarks.
+//! - [`guess`] resolves queries to directories.
+//! - [`matching`] scores queries against candidates.
+//! - [`path_cmp`] and [`path_map`] normalize paths for comparison and
+//!   between machines.
+
+mod age;
+mod cli;
+mod command;
+mod complete;
+mod config;
+mod doctor;
+mod edit;
+mod entries;
+pub mod error;
+mod exists;
+mod export;
+mod format;
+mod frecency;
+mod git;
+mod glob;
+pub mod guess;
+mod hooks;
+mod import;
+mod index_renderer;
+mod init;
+mod install;
+mod last_pick;
+mod logger;
+pub mod matching;
+mod merge;
+mod moved;
+mod open;
+pub mod path_cmp;
+pub mod path_map;
+mod pickers;
+mod porcelain;
+mod profile;
+mod progress;
+mod project;
+mod recent;
+mod remote;
+mod sort;
+mod stats;
+mod status;
+pub mod store;
+mod suggest;
+mod sync;
+mod theme;
+mod tmux;
+mod tui;
+mod undo;
+
+/// Runs the `pathmarks` command line with the arguments of the process.
+pub use cli::main;
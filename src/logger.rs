@@ -0,0 +1,69 @@
+//! Messages about what pathmarks is doing, on stderr as chosen with
+//! `--quiet` and `--verbose`, and in a log file with the level set in
+//! `PATHMARKS_LOG`.
+
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::{self, format::Writer, time::FormatTime};
+use tracing_subscriber::prelude::*;
+
+use crate::age::now;
+
+pub const LOG_ENV: &str = "PATHMARKS_LOG";
+
+/// Starts the lines of the log file with the time and the process, so
+/// commands running at once can be told apart.
+struct Stamp;
+
+impl FormatTime for Stamp {
+    fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
+        write!(w, "{} {}", now(), std::process::id())
+    }
+}
+
+/// Warnings are shown by default, `quiet` shows only errors and every
+/// `verbose` shows one level more. `file_level` is the level of `PATHMARKS_LOG`.
+pub fn init(quiet: bool, verbose: u8, file_level: Option<&str>, log_file: Option<&Path>) {
+    let stderr = match (quiet, verbose) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::INFO,
+        (false, 2) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+    // Logging must never fail the command, so a log file that cannot be
+    // opened is left out.
+    let file = file_level
+        .and_then(|level| level.parse::<LevelFilter>().ok())
+        .zip(log_file)
+        .and_then(|(level, path)| {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).ok()?;
+            }
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            Some((level, Mutex::new(file.ok()?)))
+        });
+
+    let stderr = fmt::layer()
+        .with_writer(io::stderr)
+        .with_ansi(false)
+        .without_time()
+        .with_level(false)
+        .with_target(false)
+        .with_filter(stderr);
+    let file = file.map(|(level, file)| {
+        fmt::layer()
+            .with_writer(file)
+            .with_ansi(false)
+            .with_timer(Stamp)
+            .with_filter(level)
+    });
+    let _ = tracing_subscriber::registry()
+        .with(stderr)
+        .with(file)
+        .try_init();
+}
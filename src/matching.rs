@@ -0,0 +1,213 @@
+//! Fuzzy matching of `guess`, `query` and friends, tuned with the
+//! `[matching]` table of the config or the matching options of the command
+//! line.
+
+use clap::{Args, ValueEnum};
+use nucleo_picker::nucleo::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_picker::nucleo::{self, Matcher};
+use serde::Deserialize;
+
+const MIN_SCORE: u32 = 60;
+
+/// Every setting is optional, command line options win over the config.
+#[derive(Args, Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Matching {
+    /// Lowest fuzzy score counted as a match [default: 60]
+    #[arg(long, global = true, value_name = "SCORE", help_heading = "Matching")]
+    pub min_score: Option<u32>,
+    /// How letter case is matched [default: smart]
+    #[arg(long, global = true, help_heading = "Matching")]
+    pub case: Option<Case>,
+    /// Whether letters with accents match the letter without [default: smart]
+    #[arg(long, global = true, help_heading = "Matching")]
+    pub normalization: Option<Normalize>,
+    /// Scoring tuned for paths or for plain text [default: path]
+    #[arg(long, global = true, help_heading = "Matching")]
+    pub matcher: Option<MatcherKind>,
+}
+
+/// How queries treat case, `--case`.
+#[derive(Copy, Clone, Debug, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Case {
+    /// Ignore case unless the query has an uppercase letter
+    #[default]
+    Smart,
+    Ignore,
+    Respect,
+}
+
+/// Whether queries match accented letters, `--normalization`.
+#[derive(Copy, Clone, Debug, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Normalize {
+    /// Letters without accents in the query also match accented ones
+    #[default]
+    Smart,
+    Never,
+}
+
+/// How matches are scored, `--matcher`.
+#[derive(Copy, Clone, Debug, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MatcherKind {
+    /// Favour matches at the start of path components
+    #[default]
+    Path,
+    Plain,
+}
+
+impl Matching {
+    /// These settings, with the unset ones taken from `fallback`.
+    pub fn or(self, fallback: Matching) -> Matching {
+        Matching {
+            min_score: self.min_score.or(fallback.min_score),
+            case: self.case.or(fallback.case),
+            normalization: self.normalization.or(fallback.normalization),
+            matcher: self.matcher.or(fallback.matcher),
+        }
+    }
+
+    /// Best fuzzy match of `query` in `items`, with its score.
+    pub fn best<'a, I>(&self, query: &str, items: I) -> Option<(&'a str, u32)>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.best_by(query, items, |_| 1.0)
+    }
+
+    /// Best fuzzy match, with each score multiplied by `weight` of the item.
+    pub fn best_by<'a, I>(
+        &self,
+        query: &str,
+        items: I,
+        weight: impl Fn(&str) -> f64,
+    ) -> Option<(&'a str, u32)>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.matches(query, items)
+            .into_iter()
+            .max_by(|(a_str, a_score), (b_str, b_score)| {
+                let a_weighted = f64::from(*a_score) * weight(a_str);
+                let b_weighted = f64::from(*b_score) * weight(b_str);
+                a_weighted
+                    .total_cmp(&b_weighted)
+                    .then_with(|| b_str.len().cmp(&a_str.len()))
+            })
+    }
+
+    /// Every match, best first, with its score multiplied by `weight` of the
+    /// item.
+    pub fn ranked<'a, I>(
+        &self,
+        query: &str,
+        items: I,
+        weight: impl Fn(&str) -> f64,
+    ) -> Vec<(&'a str, f64)>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut matches: Vec<_> = self
+            .matches(query, items)
+            .into_iter()
+            .map(|(item, score)| (item, f64::from(score) * weight(item)))
+            .collect();
+        matches.sort_by(|(a_str, a_score), (b_str, b_score)| {
+            b_score
+                .total_cmp(a_score)
+                .then_with(|| a_str.len().cmp(&b_str.len()))
+        });
+        matches
+    }
+
+    fn matches<'a, I>(&self, query: &str, items: I) -> Vec<(&'a str, u32)>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let config = match self.matcher.unwrap_or_default() {
+            MatcherKind::Path => nucleo::Config::DEFAULT.match_paths(),
+            MatcherKind::Plain => nucleo::Config::DEFAULT,
+        };
+        let case = match self.case.unwrap_or_default() {
+            Case::Smart => CaseMatching::Smart,
+            Case::Ignore => CaseMatching::Ignore,
+            Case::Respect => CaseMatching::Respect,
+        };
+        let normalization = match self.normalization.unwrap_or_default() {
+            Normalize::Smart => Normalization::Smart,
+            Normalize::Never => Normalization::Never,
+        };
+        let min_score = self.min_score.unwrap_or(MIN_SCORE);
+
+        Pattern::parse(query, case, normalization)
+            .match_list(items, &mut Matcher::new(config))
+            .into_iter()
+            .filter(|(_, score)| *score >= min_score)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranked_best_first() {
+        let items = ["/src/foobar", "/src/foo", "/src/bar"];
+
+        let ranked = Matching::default().ranked("foo", items, |_| 1.0);
+        let paths: Vec<_> = ranked.iter().map(|(path, _)| *path).collect();
+
+        assert_eq!(paths, ["/src/foo", "/src/foobar"]);
+        assert!(ranked[0].1 >= ranked[1].1);
+    }
+
+    #[test]
+    fn best_returns_score_and_value() {
+        let items = ["foobar", "foo", "bar"];
+
+        let result = Matching::default().best("foo", items).unwrap();
+
+        assert_eq!(result.0, "foo");
+    }
+
+    #[test]
+    fn settings_tune_matching() {
+        let items = ["/src/Foo", "/src/föo"];
+        let default = Matching::default();
+
+        assert_eq!(default.ranked("foo", items, |_| 1.0).len(), 2);
+
+        let strict = Matching {
+            case: Some(Case::Respect),
+            normalization: Some(Normalize::Never),
+            ..Default::default()
+        };
+        assert!(strict.ranked("foo", items, |_| 1.0).is_empty());
+
+        let picky = Matching {
+            min_score: Some(u32::MAX),
+            ..Default::default()
+        };
+        assert_eq!(picky.best("foo", items), None);
+    }
+
+    #[test]
+    fn command_line_wins_over_config() {
+        let cli = Matching {
+            min_score: Some(10),
+            ..Default::default()
+        };
+        let config = Matching {
+            min_score: Some(80),
+            case: Some(Case::Respect),
+            ..Default::default()
+        };
+
+        let merged = cli.or(config);
+        assert_eq!(merged.min_score, Some(10));
+        assert!(matches!(merged.case, Some(Case::Respect)));
+    }
+}
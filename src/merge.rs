@@ -0,0 +1,178 @@
+//! Combining bookmarks of the same directory from two stores, for `merge`
+//! and `sync`.
+
+use std::path::{Path, PathBuf};
+
+use crate::store::Bookmark;
+
+/// The bookmark used last, keeping the most visits and the first creation.
+pub fn newest(ours: Bookmark, theirs: Bookmark) -> Bookmark {
+    let (newer, older) = match theirs.last_used > ours.last_used {
+        true => (theirs, ours),
+        false => (ours, theirs),
+    };
+
+    Bookmark {
+        visits: newer.visits.max(older.visits),
+        created_at: match (newer.created_at, older.created_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        },
+        ..newer
+    }
+}
+
+/// What merging another store changed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub added: Vec<PathBuf>,
+    pub combined: usize,
+    /// Bookmarks both stores had with a different name, icon, opener, note
+    /// or slot, with the fields that differed. The one used last won.
+    pub conflicts: Vec<(PathBuf, Vec<&'static str>)>,
+}
+
+impl Summary {
+    pub fn plain(&self) -> String {
+        let mut lines = vec![format!(
+            "Added {}, combined {}, {} conflicts",
+            self.added.len(),
+            self.combined,
+            self.conflicts.len()
+        )];
+        lines.extend(self.added.iter().map(|p| format!("added {}", p.display())));
+        lines.extend(
+            self.conflicts
+                .iter()
+                .map(|(p, fields)| format!("conflict {}: {}", p.display(), fields.join(", "))),
+        );
+        lines.join("\n")
+    }
+}
+
+/// Adds `other` to `bookmarks`, combining the bookmarks `same` finds in both:
+/// the one used last wins, keeping what only the other has and all tags.
+/// Added bookmarks lose a slot that is taken.
+pub fn merge_into(
+    bookmarks: &mut Vec<Bookmark>,
+    other: Vec<Bookmark>,
+    same: impl Fn(&Path, &Path) -> bool,
+) -> Summary {
+    let mut summary = Summary::default();
+
+    for theirs in other {
+        let Some(i) = bookmarks.iter().position(|b| same(&b.path, &theirs.path)) else {
+            let slot = theirs
+                .slot
+                .filter(|slot| !bookmarks.iter().any(|b| b.slot == Some(*slot)));
+            summary.added.push(theirs.path.clone());
+            bookmarks.push(Bookmark { slot, ..theirs });
+            continue;
+        };
+
+        let ours = bookmarks[i].clone();
+        let fields = conflicting(&ours, &theirs);
+        if !fields.is_empty() {
+            summary.conflicts.push((ours.path.clone(), fields));
+        }
+
+        let mut tags = ours.tags.clone();
+        tags.extend(
+            theirs
+                .tags
+                .iter()
+                .filter(|t| !ours.tags.contains(t))
+                .cloned(),
+        );
+        let path = ours.path.clone();
+        // Unlike in `sync`, what only the older one has is kept.
+        let older = match theirs.last_used > ours.last_used {
+            true => ours.clone(),
+            false => theirs.clone(),
+        };
+        let newest = newest(ours, theirs);
+        bookmarks[i] = Bookmark {
+            path,
+            tags,
+            name: newest.name.or(older.name),
+            icon: newest.icon.or(older.icon),
+            opener: newest.opener.or(older.opener),
+            note: newest.note.or(older.note),
+            slot: newest.slot.or(older.slot),
+            pinned: newest.pinned || older.pinned,
+            ..newest
+        };
+        summary.combined += 1;
+    }
+
+    summary
+}
+
+fn conflicting(ours: &Bookmark, theirs: &Bookmark) -> Vec<&'static str> {
+    fn differs<T: PartialEq>(a: &Option<T>, b: &Option<T>) -> bool {
+        a.is_some() && b.is_some() && a != b
+    }
+
+    [
+        ("name", differs(&ours.name, &theirs.name)),
+        ("icon", differs(&ours.icon, &theirs.icon)),
+        ("opener", differs(&ours.opener, &theirs.opener)),
+        ("note", differs(&ours.note, &theirs.note)),
+        ("slot", differs(&ours.slot, &theirs.slot)),
+    ]
+    .into_iter()
+    .filter_map(|(field, differs)| differs.then_some(field))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(path: &str) -> Bookmark {
+        Bookmark::new(PathBuf::from(path))
+    }
+
+    #[test]
+    fn merges_another_store() {
+        let mut bookmarks = vec![
+            Bookmark {
+                name: Some("mine".to_string()),
+                tags: vec!["work".to_string()],
+                slot: Some(1),
+                last_used: Some(1),
+                ..bookmark("/shared")
+            },
+            bookmark("/mine"),
+        ];
+        let other = vec![
+            Bookmark {
+                name: Some("theirs".to_string()),
+                tags: vec!["rust".to_string(), "work".to_string()],
+                last_used: Some(2),
+                ..bookmark("/shared/")
+            },
+            Bookmark {
+                slot: Some(1),
+                ..bookmark("/theirs")
+            },
+        ];
+
+        let same = |a: &Path, b: &Path| a.components().eq(b.components());
+        let summary = merge_into(&mut bookmarks, other, same);
+
+        assert_eq!(
+            summary,
+            Summary {
+                added: vec![PathBuf::from("/theirs")],
+                combined: 1,
+                conflicts: vec![(PathBuf::from("/shared"), vec!["name"])],
+            }
+        );
+        assert_eq!(bookmarks[0].path, Path::new("/shared"));
+        assert_eq!(bookmarks[0].name.as_deref(), Some("theirs"));
+        assert_eq!(bookmarks[0].tags, ["work", "rust"]);
+        assert_eq!(bookmarks[0].slot, Some(1));
+        assert_eq!(bookmarks[2].slot, None);
+    }
+}
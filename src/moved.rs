@@ -0,0 +1,70 @@
+//! Finds where a bookmarked directory that no longer exists was moved to,
+//! so `prune` can update the bookmark instead of removing it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How many levels below the parent, or grandparent, are searched.
+const DEPTH: usize = 2;
+
+/// A directory named like the missing `path` below its parent, or also
+/// below its grandparent with `grandparent`, nearest first.
+pub fn find_moved(path: &Path, grandparent: bool) -> Option<PathBuf> {
+    let name = path.file_name()?;
+    let parent = path.parent()?;
+    let bases = if grandparent {
+        vec![parent, parent.parent()?]
+    } else {
+        vec![parent]
+    };
+
+    bases.into_iter().find_map(|base| {
+        let mut level = vec![base.to_path_buf()];
+        for _ in 0..DEPTH {
+            level = level.iter().flat_map(|dir| child_dirs(dir)).collect();
+            if let Some(found) = level.iter().find(|dir| dir.file_name() == Some(name)) {
+                return Some(found.clone());
+            }
+        }
+        None
+    })
+}
+
+/// Subdirectories of `dir`, leaving out hidden ones, sorted.
+fn child_dirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort_unstable();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_moved_directory_below_parent() {
+        let temp = tempfile::tempdir().unwrap();
+        let src = temp.path().join("src");
+        let moved = src.join("archive").join("proj");
+        fs::create_dir_all(&moved).unwrap();
+
+        assert_eq!(find_moved(&src.join("proj"), false), Some(moved));
+
+        let work = temp.path().join("work");
+        fs::create_dir_all(&work).unwrap();
+        assert_eq!(find_moved(&work.join("archive"), false), None);
+        assert_eq!(
+            find_moved(&work.join("archive"), true),
+            Some(src.join("archive"))
+        );
+    }
+}
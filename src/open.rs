@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::Path;
+
+use crate::command::{from_template, run};
+use crate::error::AppResult;
+
+#[cfg(target_os = "macos")]
+const FILE_MANAGER: &str = "open";
+#[cfg(windows)]
+const FILE_MANAGER: &str = "explorer";
+#[cfg(not(any(target_os = "macos", windows)))]
+const FILE_MANAGER: &str = "xdg-open";
+
+/// Opens `path` with `opener`, which is either the name of a configured
+/// opener or a program. Without an opener, files are opened in `$EDITOR`
+/// when it is set, and anything else with the configured `default` opener
+/// or the platform file manager.
+pub fn open(path: &Path, opener: Option<&str>, openers: &HashMap<String, String>) -> AppResult<()> {
+    let editor = env::var("EDITOR").ok().filter(|editor| !editor.is_empty());
+    if opener.is_none()
+        && path.is_file()
+        && let Some(editor) = editor
+    {
+        return run(from_template(&editor, path)?);
+    }
+
+    let name = opener.unwrap_or("default");
+    let template = match openers.get(name) {
+        Some(template) => template,
+        None => opener.unwrap_or(FILE_MANAGER),
+    };
+
+    run(from_template(template, path)?)
+}
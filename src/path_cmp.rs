@@ -0,0 +1,129 @@
+//! Path comparisons the way the platform treats paths. On Windows, paths
+//! are equal ignoring case and separator style, and the `\\?\` prefix of
+//! `canonicalize` is dropped. Elsewhere paths compare as they are.
+
+use std::path::{Path, PathBuf};
+
+const WINDOWS: bool = cfg!(windows);
+
+/// `path` without the verbatim prefix and with an uppercase drive letter.
+pub fn simplify(path: PathBuf) -> PathBuf {
+    if !WINDOWS {
+        return path;
+    }
+    PathBuf::from(simplify_windows(&path.to_string_lossy()))
+}
+
+/// Whether `a` and `b` name the same path, without touching the disk.
+pub fn same_path(a: &Path, b: &Path) -> bool {
+    if !WINDOWS {
+        return a == b;
+    }
+    same_windows(&a.to_string_lossy(), &b.to_string_lossy())
+}
+
+/// `path` relative to `base`, if it is below it or `base` itself.
+pub fn strip_base(path: &Path, base: &Path) -> Option<PathBuf> {
+    if !WINDOWS {
+        return path.strip_prefix(base).ok().map(Path::to_path_buf);
+    }
+    strip_base_windows(&path.to_string_lossy(), &base.to_string_lossy()).map(PathBuf::from)
+}
+
+fn simplify_windows(path: &str) -> String {
+    let path = match path.strip_prefix(r"\\?\") {
+        Some(rest) => match rest.strip_prefix(r"UNC\") {
+            Some(share) => format!(r"\\{share}"),
+            None => rest.to_string(),
+        },
+        None => path.to_string(),
+    };
+    let path = path.replace('/', r"\");
+
+    let mut chars = path.chars();
+    match (chars.next(), chars.next()) {
+        (Some(drive), Some(':')) => format!("{}{}", drive.to_ascii_uppercase(), &path[1..]),
+        _ => path,
+    }
+}
+
+/// The simplified path without trailing separators, except the one of a
+/// drive root like `C:\`.
+fn windows_key(path: &str) -> String {
+    let path = simplify_windows(path);
+    let trimmed = path.trim_end_matches('\\');
+    if trimmed.ends_with(':') || trimmed.is_empty() {
+        path
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn same_windows(a: &str, b: &str) -> bool {
+    let (a, b) = (windows_key(a), windows_key(b));
+    strip_prefix_ignore_case(&a, &b) == Some("")
+}
+
+fn strip_base_windows(path: &str, base: &str) -> Option<String> {
+    let (path, base) = (windows_key(path), windows_key(base));
+    let rest = strip_prefix_ignore_case(&path, &base)?;
+
+    if rest.is_empty() {
+        Some(String::new())
+    } else if base.ends_with('\\') {
+        Some(rest.to_string())
+    } else {
+        rest.strip_prefix('\\').map(String::from)
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let mut chars = s.chars();
+    for expected in prefix.chars() {
+        let found = chars.next()?;
+        if !found.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_paths_ignore_case_and_prefixes() {
+        assert_eq!(simplify_windows(r"\\?\c:\Users\Me"), r"C:\Users\Me");
+        assert_eq!(simplify_windows(r"\\?\UNC\server\share"), r"\\server\share");
+
+        assert!(same_windows(r"C:\Users\Me\", r"\\?\c:\users\me"));
+        assert!(same_windows("c:/src/Pathmarks", r"C:\SRC\pathmarks"));
+        assert!(!same_windows(r"C:\src\path", r"C:\src\pathmarks"));
+
+        assert_eq!(
+            strip_base_windows(r"C:\Src\Pathmarks\src", r"c:\src"),
+            Some(r"Pathmarks\src".to_string())
+        );
+        assert_eq!(
+            strip_base_windows(r"C:\src", r"c:\"),
+            Some("src".to_string())
+        );
+        assert_eq!(strip_base_windows(r"C:\srcs", r"C:\src"), None);
+        assert_eq!(
+            strip_base_windows(r"C:\src\", r"c:\SRC"),
+            Some(String::new())
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn other_paths_compare_as_they_are() {
+        assert!(same_path(Path::new("/src/"), Path::new("/src")));
+        assert!(!same_path(Path::new("/Src"), Path::new("/src")));
+        assert_eq!(
+            strip_base(Path::new("/src/app"), Path::new("/src")),
+            Some(PathBuf::from("app"))
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Translates paths between a container and its host, e.g.
+//! `/workspaces/app` inside a devcontainer and `~/src/app` on the host.
+//!
+//! Bookmarks are stored in their host form and shown in whichever form
+//! exists where pathmarks runs.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+use crate::path_cmp::simplify;
+
+/// A directory of a container and where it is on the host.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathMapping {
+    pub container: PathBuf,
+    pub host: PathBuf,
+}
+
+/// Translations between stored paths and paths on this machine.
+pub struct PathMap<'a> {
+    pub mappings: &'a [PathMapping],
+    /// Store Windows paths in their WSL form, and show each stored path in
+    /// the form of the side pathmarks runs on.
+    pub wsl: bool,
+}
+
+/// The form of `path` that is written to the store.
+pub fn to_store(path_map: &PathMap, path: PathBuf) -> PathBuf {
+    let path = path_map
+        .mappings
+        .iter()
+        .find_map(|m| replace_prefix(&path, &expand_home(&m.container), &expand_home(&m.host)))
+        .unwrap_or(path);
+
+    if path_map.wsl
+        && let Some(wsl) = path.to_str().and_then(windows_to_wsl)
+    {
+        return wsl;
+    }
+    path
+}
+
+/// The form of a stored `path` that exists on this machine.
+pub fn to_local(path_map: &PathMap, path: PathBuf) -> PathBuf {
+    if (path_map.mappings.is_empty() && !path_map.wsl) || path.exists() {
+        return path;
+    }
+
+    let wsl = |path: &Path| {
+        let path = path.to_str()?;
+        if cfg!(windows) {
+            wsl_to_windows(path)
+        } else {
+            windows_to_wsl(path)
+        }
+    };
+
+    path_map
+        .mappings
+        .iter()
+        .find_map(|m| replace_prefix(&path, &expand_home(&m.host), &expand_home(&m.container)))
+        .filter(|local| local.exists())
+        .or_else(|| wsl(&path).filter(|local| path_map.wsl && local.exists()))
+        .unwrap_or(path)
+}
+
+/// `C:\Users\me` the way WSL mounts it, `/mnt/c/Users/me`.
+fn windows_to_wsl(path: &str) -> Option<PathBuf> {
+    let (drive, rest) = path.split_once(':')?;
+    let drive = single_letter(drive)?.to_ascii_lowercase();
+
+    let rest = rest.replace('\\', "/");
+    let rest = rest.trim_start_matches('/');
+    if rest.is_empty() {
+        Some(PathBuf::from(format!("/mnt/{drive}")))
+    } else {
+        Some(PathBuf::from(format!("/mnt/{drive}/{rest}")))
+    }
+}
+
+/// `/mnt/c/Users/me` of WSL the way Windows names it, `C:\Users\me`.
+fn wsl_to_windows(path: &str) -> Option<PathBuf> {
+    let rest = path.strip_prefix("/mnt/")?;
+    let (drive, rest) = rest.split_once('/').unwrap_or((rest, ""));
+    let drive = single_letter(drive)?.to_ascii_uppercase();
+
+    Some(PathBuf::from(format!(
+        "{drive}:\\{}",
+        rest.trim_end_matches('/').replace('/', "\\")
+    )))
+}
+
+fn single_letter(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_alphabetic() => Some(letter),
+        _ => None,
+    }
+}
+
+fn replace_prefix(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    let rest = path.strip_prefix(from).ok()?;
+
+    if rest.as_os_str().is_empty() {
+        Some(to.to_path_buf())
+    } else {
+        Some(to.join(rest))
+    }
+}
+
+/// `path` with a leading `~` replaced by the home directory.
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Resolves `.` and `..` and drops trailing slashes without looking at the
+/// file system, so symlinks are kept.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// `path` the way it is bookmarked: canonical, or only normalized when
+/// symlinks are kept.
+pub fn bookmark_path(path: &Path, keep_symlinks: bool) -> io::Result<PathBuf> {
+    if !keep_symlinks {
+        return path.canonicalize().map(simplify);
+    }
+
+    let normalized = normalize(path);
+    fs::metadata(&normalized)?;
+    Ok(normalized)
+}
+
+static VARIABLE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$(?:\{(\w+)\}|(\w+))").unwrap());
+
+/// Expands a leading `~` and `$VAR` or `${VAR}` anywhere in `path`. Unset
+/// variables are left as they are.
+pub fn expand(path: &str) -> PathBuf {
+    let expanded = VARIABLE.replace_all(path, |caps: &Captures| {
+        let name = caps.get(1).or(caps.get(2)).map_or("", |m| m.as_str());
+        env::var(name).unwrap_or_else(|_| caps[0].to_string())
+    });
+    expand_home(Path::new(expanded.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn container_paths_are_stored_as_host_paths() {
+        let mappings = [PathMapping {
+            container: PathBuf::from("/workspaces/app"),
+            host: PathBuf::from("/home/me/src/app"),
+        }];
+        let path_map = PathMap {
+            mappings: &mappings,
+            wsl: false,
+        };
+
+        assert_eq!(
+            to_store(&path_map, PathBuf::from("/workspaces/app/api")),
+            PathBuf::from("/home/me/src/app/api")
+        );
+        assert_eq!(
+            to_store(&path_map, PathBuf::from("/workspaces/other")),
+            PathBuf::from("/workspaces/other")
+        );
+    }
+
+    #[test]
+    fn normalizes_without_resolving() {
+        assert_eq!(
+            normalize(Path::new("/home/me/./link/../proj/")),
+            PathBuf::from("/home/me/proj")
+        );
+        assert_eq!(normalize(Path::new("/..")), PathBuf::from("/"));
+    }
+
+    #[test]
+    fn expands_home_and_variables() {
+        let home = dirs::home_dir().unwrap();
+        let path = env::var("PATH").unwrap();
+
+        assert_eq!(expand("~/src"), home.join("src"));
+        assert_eq!(expand("$PATH/x"), PathBuf::from(format!("{path}/x")));
+        assert_eq!(expand("${PATH}x"), PathBuf::from(format!("{path}x")));
+        assert_eq!(
+            expand("/a/$PATHMARKS_UNSET_VARIABLE"),
+            PathBuf::from("/a/$PATHMARKS_UNSET_VARIABLE")
+        );
+        assert_eq!(expand("~user/src"), PathBuf::from("~user/src"));
+    }
+
+    #[test]
+    fn host_paths_resolve_inside_container() {
+        let temp = tempfile::tempdir().unwrap();
+        let container = temp.path().join("workspaces").join("app");
+        fs::create_dir_all(container.join("api")).unwrap();
+
+        let mappings = [PathMapping {
+            container: container.clone(),
+            host: PathBuf::from("/nonexistent/src/app"),
+        }];
+        let path_map = PathMap {
+            mappings: &mappings,
+            wsl: false,
+        };
+
+        assert_eq!(
+            to_local(&path_map, PathBuf::from("/nonexistent/src/app/api")),
+            container.join("api")
+        );
+        assert_eq!(
+            to_local(&path_map, PathBuf::from("/nonexistent/src/other")),
+            PathBuf::from("/nonexistent/src/other")
+        );
+    }
+
+    #[test]
+    fn translates_between_wsl_and_windows() {
+        assert_eq!(
+            windows_to_wsl(r"C:\Users\me\src"),
+            Some(PathBuf::from("/mnt/c/Users/me/src"))
+        );
+        assert_eq!(windows_to_wsl("d:/"), Some(PathBuf::from("/mnt/d")));
+        assert_eq!(windows_to_wsl("/home/me/a:b"), None);
+
+        assert_eq!(
+            wsl_to_windows("/mnt/c/Users/me/"),
+            Some(PathBuf::from(r"C:\Users\me"))
+        );
+        assert_eq!(wsl_to_windows("/mnt/d"), Some(PathBuf::from(r"D:\")));
+        assert_eq!(wsl_to_windows("/mnt/wsl/share"), None);
+    }
+}
@@ -1,78 +1,251 @@
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use nucleo_picker::event::{Event, keybind_no_multi};
+use nucleo_picker::nucleo::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_picker::nucleo::{Config, Matcher, Utf32Str};
+use nucleo_picker::{Picker, PickerOptions, Render};
+
+use crate::error::{AppError, AppResult};
+use crate::index_renderer::{self, IndexPathRenderer};
+use crate::store::Bookmark;
+use crate::theme::{Style, theme};
+
+/// Picks a bookmark, failing with [`AppError::Cancelled`] when the picker is
+/// closed without one.
+pub fn pick_one(bookmarks: &[Bookmark]) -> AppResult<&Bookmark> {
+    let mut picker = Picker::new(IndexPathRenderer::new(bookmarks));
+    let mut injector = picker.injector();
+    injector.extend(0..bookmarks.len());
+
+    let selected_idx = picker.pick()?.copied().ok_or(AppError::Cancelled)?;
 
-use nucleo_picker::{Picker, Render};
+    Ok(&bookmarks[selected_idx])
+}
 
-use crate::{error::AppResult, index_renderer::IndexPathRenderer};
+/// Picks any number of bookmarks, queued with tab. Without queued
+/// entries, the highlighted one is picked.
+pub fn pick_many(bookmarks: &[Bookmark]) -> AppResult<Vec<&Bookmark>> {
+    pick_many_matching(bookmarks, &Query::default())
+}
 
-pub fn pick_one(bookmarks: &[PathBuf]) -> AppResult<Option<&PathBuf>> {
-    let mut picker = Picker::new(IndexPathRenderer::new(bookmarks));
+/// [`pick_many`] starting with `query` in the search box.
+pub fn pick_many_matching<'a>(
+    bookmarks: &'a [Bookmark],
+    query: &Query,
+) -> AppResult<Vec<&'a Bookmark>> {
+    let renderer = IndexPathRenderer::new(bookmarks);
+    if query.select_1
+        && let Some(i) = only_match(query.text, (0..bookmarks.len()).map(|i| renderer.label(i)))
+    {
+        return Ok(vec![&bookmarks[i]]);
+    }
+
+    let mut picker = PickerOptions::new().query(query.text).picker(renderer);
     let mut injector = picker.injector();
     injector.extend(0..bookmarks.len());
 
-    let selected_idx = picker.pick()?.copied();
+    let selection = picker.pick_multi()?;
+    if selection.is_empty() {
+        return Err(AppError::Cancelled);
+    }
+
+    Ok(selection.iter().map(|&i| &bookmarks[i]).collect())
+}
 
-    Ok(selected_idx.map(|i| &bookmarks[i]))
+/// What the picker starts with.
+#[derive(Default)]
+pub struct Query<'a> {
+    /// Text put in the search box.
+    pub text: &'a str,
+    /// Whether the only entry matching `text` is picked without opening the
+    /// picker.
+    pub select_1: bool,
 }
 
-#[derive(Clone, Copy)]
-enum Source {
-    First,
-    Second,
+/// The index of the only text matching `query` the way the picker matches,
+/// if exactly one does.
+fn only_match(query: &str, texts: impl Iterator<Item = String>) -> Option<usize> {
+    let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+    let mut matcher = Matcher::new(Config::DEFAULT);
+    let mut buf = Vec::new();
+
+    let mut matches = texts.enumerate().filter(|(_, text)| {
+        pattern
+            .score(Utf32Str::new(text, &mut buf), &mut matcher)
+            .is_some()
+    });
+    let (only, _) = matches.next()?;
+    matches.next().is_none().then_some(only)
 }
 
 struct Entry<'a> {
     path: &'a PathBuf,
-    source: Source,
+    /// Missing for the directories of the current one.
+    bookmark: Option<&'a Bookmark>,
+    file: bool,
+    style: &'a Style,
+}
+
+/// An entry chosen in [`pick_one_last_dim`].
+pub enum Picked {
+    Select(PathBuf),
+    /// The entry was chosen with ctrl-d, to be deleted.
+    Delete(PathBuf),
+}
+
+/// What the picker of [`pick_one_last_dim`] offers.
+enum Item {
+    /// An entry of the lists, by its index.
+    Entry(usize),
+    /// A path found while the picker is open.
+    Later(PathBuf),
 }
 
+/// Picks from both lists, styled as directories of the current one and
+/// bookmarks after them. `highlighted` is
+/// placed first so it is selected when the picker opens. `later` is read
+/// while the picker is open and added undimmed, so a slow iterator does not
+/// hold up the picker, unless `query` picks the only match without it.
 pub fn pick_one_last_dim<'a>(
     first: &'a [PathBuf],
-    second: &'a [PathBuf],
-) -> AppResult<Option<&'a PathBuf>> {
-    let entries: Vec<Entry<'a>> = first
+    second: &'a [Bookmark],
+    highlighted: Option<&Path>,
+    mut later: impl Iterator<Item = PathBuf> + Send,
+    query: &Query,
+) -> AppResult<Picked> {
+    let theme = theme(&io::stderr());
+    let after_dirs = !first.is_empty();
+    let mut entries: Vec<Entry<'a>> = first
         .iter()
         .map(|p| Entry {
             path: p,
-            source: Source::First,
+            bookmark: None,
+            file: false,
+            style: theme.entry(p, false, true, false),
         })
-        .chain(second.iter().map(|p| Entry {
-            path: p,
-            source: Source::Second,
+        .chain(second.iter().map(|b| Entry {
+            path: &b.path,
+            bookmark: Some(b),
+            file: b.path.is_file(),
+            style: theme.entry(&b.path, b.pinned, false, after_dirs),
         }))
         .collect();
 
-    let renderer = DualListIndexRenderer { entries: &entries };
+    if let Some(idx) = highlighted.and_then(|h| entries.iter().position(|e| e.path == h)) {
+        entries[..=idx].rotate_right(1);
+    }
+
+    // Every entry has to be known to tell whether only one matches.
+    let mut found = Vec::new();
+    if query.select_1 {
+        found.extend(later.by_ref());
+        let labels = entries.iter().map(label);
+        let found_labels = found.iter().map(|path| path.to_string_lossy().into_owned());
+        if let Some(i) = only_match(query.text, labels.chain(found_labels)) {
+            let path = match entries.get(i) {
+                Some(entry) => entry.path.clone(),
+                None => found[i - entries.len()].clone(),
+            };
+            return Ok(Picked::Select(path));
+        }
+    }
+    let later = found.into_iter().chain(later);
 
-    let mut picker = Picker::new(renderer);
+    let renderer = DualListIndexRenderer {
+        entries: &entries,
+        later: &theme.dir,
+    };
+
+    let mut picker = PickerOptions::new().query(query.text).picker(renderer);
     let mut injector = picker.injector();
 
-    injector.extend(0..entries.len());
+    injector.extend((0..entries.len()).map(Item::Entry));
+
+    let mut delete = false;
+    let done = AtomicBool::new(false);
+    let selected = thread::scope(|scope| {
+        scope.spawn(|| {
+            for path in later {
+                if done.load(Ordering::Relaxed) {
+                    break;
+                }
+                injector.push(Item::Later(path));
+            }
+        });
+
+        let selected = picker.pick_with_keybind(|key| match key {
+            KeyEvent {
+                code: KeyCode::Char('d'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            } => {
+                delete = true;
+                Some(Event::Select)
+            }
+            key => keybind_no_multi(key),
+        });
+        done.store(true, Ordering::Relaxed);
+
+        selected.map(|item| match item {
+            Some(Item::Entry(i)) => Some(entries[*i].path.clone()),
+            Some(Item::Later(path)) => Some(path.clone()),
+            None => None,
+        })
+    })?
+    .ok_or(AppError::Cancelled)?;
 
-    let selected_idx = picker.pick()?.copied();
-    Ok(selected_idx.map(|i| entries[i].path))
+    if delete {
+        Ok(Picked::Delete(selected))
+    } else {
+        Ok(Picked::Select(selected))
+    }
 }
 
 pub struct DualListIndexRenderer<'a> {
     entries: &'a [Entry<'a>],
+    /// Style of the paths found while the picker is open.
+    later: &'a Style,
 }
 
-impl<'a> Render<usize> for DualListIndexRenderer<'a> {
+impl<'a> Render<Item> for DualListIndexRenderer<'a> {
     type Str<'b>
         = String
     where
-        usize: 'b;
+        Item: 'b;
+
+    fn render<'b>(&self, item: &'b Item) -> Self::Str<'b> {
+        let entry = match item {
+            Item::Entry(i) => &self.entries[*i],
+            Item::Later(path) => return self.later.paint(&path.to_string_lossy()),
+        };
+        entry.style.paint(&label(entry))
+    }
+}
 
-    fn render<'b>(&self, idx: &'b usize) -> Self::Str<'b> {
-        let entry = &self.entries[*idx];
-        let path = entry.path.to_string_lossy();
+/// How an entry is shown, without styling.
+fn label(entry: &Entry) -> String {
+    match entry.bookmark {
+        Some(bookmark) => index_renderer::label(bookmark, entry.file),
+        None => entry.path.to_string_lossy().into_owned(),
+    }
+}
 
-        const ITALIC: &str = "\x1b[3m";
-        const DIM: &str = "\x1b[2m";
-        const RESET: &str = "\x1b[0m";
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        match entry.source {
-            Source::First => path.to_string(),
-            Source::Second => format!("{DIM}{ITALIC}{path}{RESET}"),
-        }
+    #[test]
+    fn select_1_needs_exactly_one_match() {
+        let texts = || ["/src/pathmarks", "/src/pathfinder", "/home/me"].map(String::from);
+
+        assert_eq!(only_match("marks", texts().into_iter()), Some(0));
+        assert_eq!(only_match("/home", texts().into_iter()), Some(2));
+        assert_eq!(only_match("path", texts().into_iter()), None);
+        assert_eq!(only_match("nothing", texts().into_iter()), None);
     }
 }
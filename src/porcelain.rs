@@ -0,0 +1,126 @@
+//! Stable, script-friendly output formats.
+//!
+//! Each version is frozen once released: new fields are only ever added
+//! through a new version, so `--porcelain=v1` output stays parseable forever.
+//!
+//! ## v1
+//! One line per bookmark, fields separated by a tab:
+//!
+//! ```text
+//! <absolute path>\t<exists>
+//! ```
+//!
+//! `exists` is `1` if the path currently exists and `0` otherwise.
+//!
+//! ## v2
+//! For file manager plugins, one line per bookmark, fields separated by a
+//! tab:
+//!
+//! ```text
+//! <absolute path>\t<name>\t<tags>\t<score>
+//! ```
+//!
+//! `name` is empty for unnamed bookmarks, `tags` is a comma separated list
+//! that is empty without tags, and `score` is the frecency as a decimal
+//! number, higher for bookmarks used more and more recently. Names and tags
+//! never contain tabs or newlines.
+//!
+//! Every version ends records with a NUL byte instead of a newline with
+//! `--print0`.
+//!
+//! `list` prints every bookmark it shows and `query` its matches, best
+//! first.
+//!
+//! ## stats
+//! `stats` prints the same records for every version, one per line, fields
+//! separated by a tab:
+//!
+//! ```text
+//! bookmarks\t<count>
+//! missing\t<count>
+//! store_size\t<bytes>
+//! most_used\t<absolute path>\t<visits>\t<last visit>
+//! least_used\t<absolute path>\t<visits>\t<last visit>
+//! ```
+//!
+//! There are up to five `most_used` and `least_used` records. `last visit` is
+//! in seconds since the unix epoch and empty for bookmarks never visited.
+
+use clap::ValueEnum;
+
+use crate::frecency::frecency;
+use crate::store::Bookmark;
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum Porcelain {
+    V1,
+    V2,
+}
+
+/// One record per bookmark, without the line ending.
+pub fn porcelain(version: Porcelain, bookmarks: &[Bookmark], now: u64) -> Vec<String> {
+    match version {
+        Porcelain::V1 => bookmarks.iter().map(v1).collect(),
+        Porcelain::V2 => bookmarks.iter().map(|b| v2(b, now)).collect(),
+    }
+}
+
+fn v1(bookmark: &Bookmark) -> String {
+    let exists = if bookmark.path.exists() { 1 } else { 0 };
+    format!("{}\t{exists}", bookmark.path.display())
+}
+
+fn v2(bookmark: &Bookmark, now: u64) -> String {
+    format!(
+        "{}\t{}\t{}\t{}",
+        bookmark.path.display(),
+        bookmark.name.as_deref().unwrap_or_default(),
+        bookmark.tags.join(","),
+        frecency(bookmark, now)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v1_is_tab_separated() {
+        let temp = tempfile::tempdir().unwrap();
+        let existing = temp.path().to_path_buf();
+        let missing = existing.join("missing");
+
+        let bookmarks = [
+            Bookmark::new(existing.clone()),
+            Bookmark::new(missing.clone()),
+        ];
+
+        let out = porcelain(Porcelain::V1, &bookmarks, 0);
+
+        assert_eq!(
+            out,
+            [
+                format!("{}\t1", existing.display()),
+                format!("{}\t0", missing.display())
+            ]
+        );
+    }
+
+    #[test]
+    fn v2_has_names_tags_and_scores() {
+        let bookmarks = [
+            Bookmark {
+                name: Some("api".to_string()),
+                tags: vec!["work".to_string(), "rust".to_string()],
+                visits: Some(3),
+                last_used: Some(100),
+                ..Bookmark::new("/src/api".into())
+            },
+            Bookmark::new("/tmp".into()),
+        ];
+
+        let out = porcelain(Porcelain::V2, &bookmarks, 100);
+
+        assert_eq!(out, ["/src/api\tapi\twork,rust\t12", "/tmp\t\t\t0"]);
+    }
+}
@@ -0,0 +1,57 @@
+//! Separate sets of bookmarks, e.g. for work and personal use. Every profile
+//! is a directory below `profiles` in the data directory, holding the same
+//! files the data directory holds for the default profile.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// The directory holding the bookmarks of `profile`.
+pub fn profile_dir(data_dir: &Path, profile: &str) -> AppResult<PathBuf> {
+    if profile == DEFAULT_PROFILE {
+        return Ok(data_dir.to_path_buf());
+    }
+    if profile.is_empty() || profile.starts_with('.') || profile.contains(['/', '\\']) {
+        return Err(AppError::InvalidProfile(profile.to_string()));
+    }
+
+    Ok(data_dir.join("profiles").join(profile))
+}
+
+/// The default profile followed by every other profile that exists.
+pub fn profiles(data_dir: &Path) -> Vec<String> {
+    let mut others: Vec<_> = fs::read_dir(data_dir.join("profiles"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    others.sort();
+
+    std::iter::once(DEFAULT_PROFILE.to_string())
+        .chain(others)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_live_in_their_own_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let data = temp.path();
+
+        assert_eq!(profile_dir(data, "default").unwrap(), data);
+        assert!(profile_dir(data, "../escape").is_err());
+
+        fs::create_dir_all(profile_dir(data, "work").unwrap()).unwrap();
+        fs::create_dir_all(profile_dir(data, "home").unwrap()).unwrap();
+
+        assert_eq!(profiles(data), ["default", "home", "work"]);
+    }
+}
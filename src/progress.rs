@@ -0,0 +1,52 @@
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use signal_hook::consts::SIGINT;
+use signal_hook::flag;
+
+/// Single line progress report on stderr, shown only when stderr is a
+/// terminal and `shown`.
+pub struct Progress {
+    total: usize,
+    enabled: bool,
+}
+
+impl Progress {
+    pub fn new(total: usize, shown: bool) -> Self {
+        Self {
+            total,
+            enabled: shown && io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn update(&self, done: usize, current: &Path) {
+        if self.enabled {
+            eprint!("\r\x1b[2K[{done}/{}] {}", self.total, current.display());
+        }
+    }
+
+    pub fn finish(&self) {
+        if self.enabled {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+/// Raised by the first ctrl-c so long running work can stop early and keep
+/// what is done. A second ctrl-c terminates immediately.
+pub struct Interrupt(Arc<AtomicBool>);
+
+impl Interrupt {
+    pub fn register() -> io::Result<Self> {
+        let raised = Arc::new(AtomicBool::new(false));
+        flag::register_conditional_shutdown(SIGINT, 1, raised.clone())?;
+        flag::register(SIGINT, raised.clone())?;
+        Ok(Self(raised))
+    }
+
+    pub fn raised(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,141 @@
+//! Bookmarks checked in next to the code, in `.pathmarks` files.
+//!
+//! Entries are stored relative to the directory holding the file and
+//! resolved against wherever that directory is checked out, so they work in
+//! every clone or worktree. `save --project` writes to the file at the
+//! repository root, but a `.pathmarks` file in any directory above the
+//! current one is read.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+use crate::git::repo_root;
+use crate::store::{Bookmark, read_bookmarks, write_bookmarks};
+
+const PROJECT_FILE: &str = ".pathmarks";
+
+/// Bookmarks of every `.pathmarks` file in `current_dir` and its ancestors,
+/// nearest first, as absolute paths.
+pub fn project_bookmarks(current_dir: &Path) -> AppResult<Vec<Bookmark>> {
+    let mut bookmarks: Vec<Bookmark> = Vec::new();
+
+    for dir in current_dir.ancestors() {
+        let file = dir.join(PROJECT_FILE);
+        if !file.is_file() {
+            continue;
+        }
+
+        for bookmark in read_bookmarks(&file)? {
+            let path = resolve(dir, &bookmark.path);
+            if !bookmarks.iter().any(|b| b.path == path) {
+                bookmarks.push(Bookmark { path, ..bookmark });
+            }
+        }
+    }
+
+    Ok(bookmarks)
+}
+
+/// Adds `dir` to the `.pathmarks` file of its repository, with the name,
+/// tags and note of `saved`.
+pub fn save_project_bookmark(dir: &Path, saved: Bookmark) -> AppResult<()> {
+    let root = repo_root(dir).ok_or(AppError::NotInRepository)?;
+    let file = root.join(PROJECT_FILE);
+
+    let relative = match dir.strip_prefix(&root) {
+        Ok(rel) if rel.as_os_str().is_empty() => PathBuf::from("."),
+        Ok(rel) => rel.to_path_buf(),
+        Err(_) => return Err(AppError::NotInRepository),
+    };
+
+    let mut bookmarks = if file.exists() {
+        read_bookmarks(&file)?
+    } else {
+        Vec::new()
+    };
+
+    let mut saved = Bookmark {
+        path: relative,
+        ..saved
+    };
+    match bookmarks.iter_mut().find(|b| b.path == saved.path) {
+        Some(existing) => {
+            saved.merge(existing.clone());
+            *existing = saved;
+        }
+        None => bookmarks.push(saved),
+    }
+
+    write_bookmarks(&bookmarks, &file)
+}
+
+fn resolve(root: &Path, path: &Path) -> PathBuf {
+    if path == Path::new(".") {
+        root.to_path_buf()
+    } else {
+        root.join(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn project_bookmarks_resolve_against_repository() {
+        let temp = tempfile::tempdir().unwrap();
+        let clone = temp.path().join("clone");
+        let deep = clone.join("services").join("api");
+
+        fs::create_dir_all(clone.join(".git")).unwrap();
+        fs::create_dir_all(&deep).unwrap();
+
+        let named = Bookmark {
+            name: Some("api".to_string()),
+            ..Default::default()
+        };
+        save_project_bookmark(&deep, named).unwrap();
+        save_project_bookmark(&clone, Bookmark::default()).unwrap();
+
+        let content = fs::read_to_string(clone.join(PROJECT_FILE)).unwrap();
+        assert_eq!(content, "services/api\tname=api\n.\n");
+
+        let worktree = temp.path().join("worktree");
+        fs::create_dir_all(worktree.join(".git")).unwrap();
+        fs::copy(clone.join(PROJECT_FILE), worktree.join(PROJECT_FILE)).unwrap();
+
+        let paths: Vec<_> = project_bookmarks(&worktree)
+            .unwrap()
+            .into_iter()
+            .map(|b| b.path)
+            .collect();
+        assert_eq!(paths, [worktree.join("services").join("api"), worktree]);
+    }
+
+    #[test]
+    fn project_files_in_ancestors_are_merged() {
+        let temp = tempfile::tempdir().unwrap();
+        let team = temp.path().join("team");
+        let app = team.join("app");
+        fs::create_dir_all(&app).unwrap();
+
+        fs::write(team.join(PROJECT_FILE), "shared\napp/docs\n").unwrap();
+        fs::write(app.join(PROJECT_FILE), "docs\tname=docs\n").unwrap();
+
+        let bookmarks = project_bookmarks(&app).unwrap();
+        let paths: Vec<_> = bookmarks.iter().map(|b| b.path.clone()).collect();
+
+        assert_eq!(paths, [app.join("docs"), team.join("shared")]);
+        assert_eq!(bookmarks[0].name.as_deref(), Some("docs"));
+    }
+
+    #[test]
+    fn outside_repository_has_no_project_bookmarks() {
+        let temp = tempfile::tempdir().unwrap();
+
+        assert!(project_bookmarks(temp.path()).unwrap().is_empty());
+        assert!(save_project_bookmark(temp.path(), Bookmark::default()).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Remembers recently visited directories, bookmarked or not, as recorded
+//! by the shell hook calling `pathmarks visit`, and bookmarks the ones
+//! visited often when [`Promote`] is configured.
+//!
+//! Stored next to the bookmarks file as `<times>\t<directory>` lines, most
+//! recent first, with the times of the last visits separated by commas.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::age::parse_age;
+use crate::error::AppResult;
+use crate::store::write_atomic;
+
+const MAX_RECENT: usize = 200;
+/// Visits remembered per directory, the most [`Promote`] can ask for.
+const MAX_TIMES: usize = 32;
+
+/// Bookmarks a directory once it is visited `visits` times `within` an age,
+/// the `[promote]` table of the config.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Promote {
+    pub visits: usize,
+    #[serde(default = "default_within", deserialize_with = "age")]
+    pub within: u64,
+}
+
+impl Promote {
+    /// Whether visits at `times` make a directory a bookmark.
+    pub fn due(&self, times: &[u64], now: u64) -> bool {
+        let recent = times
+            .iter()
+            .filter(|&&time| now.saturating_sub(time) <= self.within)
+            .count();
+        recent >= self.visits.clamp(1, MAX_TIMES)
+    }
+}
+
+fn default_within() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+fn age<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let age = String::deserialize(deserializer)?;
+    parse_age(&age).map_err(serde::de::Error::custom)
+}
+
+pub fn recent_file(bookmarks_file: &Path) -> PathBuf {
+    bookmarks_file.with_file_name("recent.txt")
+}
+
+/// Recently visited directories with the time of the last visit, most
+/// recent first.
+pub fn read_recent(file: &Path) -> Vec<(PathBuf, u64)> {
+    read_visits(file)
+        .into_iter()
+        .map(|(dir, times)| (dir, times[0]))
+        .collect()
+}
+
+/// Recently visited directories with the times of their last visits. A
+/// directory listed twice, e.g. by shells writing at once, is merged into
+/// one entry.
+fn read_visits(file: &Path) -> Vec<(PathBuf, Vec<u64>)> {
+    let content = fs::read_to_string(file).unwrap_or_default();
+
+    let mut visits: Vec<(PathBuf, Vec<u64>)> = Vec::new();
+    for line in content.lines() {
+        let Some((times, dir)) = line.split_once('\t') else {
+            continue;
+        };
+        let Ok(times) = times
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            continue;
+        };
+        match visits.iter_mut().find(|(d, _)| d.as_os_str() == dir) {
+            Some((_, merged)) => {
+                merged.extend(times);
+                merged.sort_by(|a, b| b.cmp(a));
+                merged.truncate(MAX_TIMES);
+            }
+            None => visits.push((PathBuf::from(dir), times)),
+        }
+    }
+    visits
+}
+
+/// Records a visit to `dir`, returning the times of its last visits, this
+/// one first.
+pub fn record_visit(file: &Path, dir: &Path, time: u64) -> AppResult<Vec<u64>> {
+    let mut visits = read_visits(file);
+    let mut times = match visits.iter().position(|(d, _)| d == dir) {
+        Some(i) => visits.remove(i).1,
+        None => Vec::new(),
+    };
+    times.insert(0, time);
+    times.truncate(MAX_TIMES);
+    visits.insert(0, (dir.to_path_buf(), times.clone()));
+
+    write_visits(file, visits)?;
+    Ok(times)
+}
+
+/// Counts the visits to `dir` from scratch, so a promoted directory that is
+/// removed again is not bookmarked on the next visit.
+pub fn restart_count(file: &Path, dir: &Path) -> AppResult<()> {
+    let mut visits = read_visits(file);
+    let Some((_, times)) = visits.iter_mut().find(|(d, _)| d == dir) else {
+        return Ok(());
+    };
+    times.truncate(1);
+
+    write_visits(file, visits)
+}
+
+fn write_visits(file: &Path, visits: Vec<(PathBuf, Vec<u64>)>) -> AppResult<()> {
+    let lines: Vec<_> = visits
+        .into_iter()
+        .take(MAX_RECENT)
+        .map(|(dir, times)| {
+            let times: Vec<_> = times.iter().map(u64::to_string).collect();
+            format!("{}\t{}\n", times.join(","), dir.display())
+        })
+        .collect();
+
+    write_atomic(lines.concat().as_bytes(), file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_recent_first_without_duplicates() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("recent.txt");
+
+        record_visit(&file, Path::new("/a"), 1).unwrap();
+        record_visit(&file, Path::new("/b"), 2).unwrap();
+        assert_eq!(record_visit(&file, Path::new("/a"), 3).unwrap(), [3, 1]);
+
+        assert_eq!(
+            read_recent(&file),
+            [(PathBuf::from("/a"), 3), (PathBuf::from("/b"), 2)]
+        );
+
+        fs::write(&file, "3\t/a\n2\t/b\n1\t/a\n").unwrap();
+        assert_eq!(read_visits(&file)[0], (PathBuf::from("/a"), vec![3, 1]));
+
+        restart_count(&file, Path::new("/a")).unwrap();
+        assert_eq!(record_visit(&file, Path::new("/a"), 4).unwrap(), [4, 3]);
+    }
+
+    #[test]
+    fn promotes_after_enough_recent_visits() {
+        let promote: Promote = toml::from_str("visits = 3\nwithin = \"1d\"").unwrap();
+        let day = 24 * 60 * 60;
+
+        assert!(promote.due(&[day + 10, day + 5, day], day + 10));
+        assert!(!promote.due(&[day + 10, day + 5], day + 10));
+        assert!(!promote.due(&[3 * day, day + 5, 0], 3 * day));
+    }
+}
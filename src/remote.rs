@@ -0,0 +1,40 @@
+//! Bookmarks of directories on other machines, written like
+//! `ssh://devbox/home/me/project`. They are stored and printed as they are,
+//! for the shell to connect to instead of changing directory, and never
+//! checked on this machine.
+
+use std::path::Path;
+
+const SSH: &str = "ssh://";
+
+pub fn is_remote(path: &Path) -> bool {
+    path.to_str().is_some_and(|path| path.starts_with(SSH))
+}
+
+/// The host and absolute path of an `ssh://` bookmark.
+pub fn parse(bookmark: &str) -> Option<(&str, &str)> {
+    let rest = bookmark.strip_prefix(SSH)?;
+    let slash = rest.find('/')?;
+    let (host, path) = rest.split_at(slash);
+    (!host.is_empty()).then_some((host, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ssh_bookmarks() {
+        assert_eq!(
+            parse("ssh://devbox/home/me/project"),
+            Some(("devbox", "/home/me/project"))
+        );
+        assert_eq!(parse("ssh://me@devbox/"), Some(("me@devbox", "/")));
+        assert_eq!(parse("ssh://devbox"), None);
+        assert_eq!(parse("ssh:///home"), None);
+        assert_eq!(parse("/home/me"), None);
+
+        assert!(is_remote(Path::new("ssh://devbox/home")));
+        assert!(!is_remote(Path::new("/ssh://devbox")));
+    }
+}
@@ -0,0 +1,87 @@
+//! Orders of `list --sort`. Pinned bookmarks come first in every order.
+
+use std::cmp::Reverse;
+
+use clap::ValueEnum;
+
+use crate::frecency::sort_by_frecency;
+use crate::store::Bookmark;
+
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+pub enum Sort {
+    /// The order bookmarks were saved in, changed with `pathmarks move`
+    #[default]
+    Manual,
+    /// By path
+    Alpha,
+    /// Most recently jumped to first
+    Recent,
+    /// Most visited, weighted by how recent, first
+    Frecency,
+}
+
+pub fn sort(bookmarks: &mut [Bookmark], order: Sort, now: u64) {
+    match order {
+        Sort::Manual => bookmarks.sort_by_key(|b| !b.pinned),
+        Sort::Alpha => bookmarks.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(a.path.cmp(&b.path))),
+        Sort::Recent => bookmarks.sort_by_key(|b| (!b.pinned, Reverse(b.last_used))),
+        Sort::Frecency => sort_by_frecency(bookmarks, now),
+    }
+}
+
+/// Moves the bookmark at `from` so it ends up at `to`, or last when `to` is
+/// past the end.
+pub fn move_to(bookmarks: &mut Vec<Bookmark>, from: usize, to: usize) {
+    let bookmark = bookmarks.remove(from);
+    bookmarks.insert(to.min(bookmarks.len()), bookmark);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn paths(bookmarks: &[Bookmark]) -> Vec<&str> {
+        bookmarks.iter().filter_map(|b| b.path.to_str()).collect()
+    }
+
+    #[test]
+    fn orders() {
+        let mut bookmarks = vec![
+            Bookmark {
+                last_used: Some(5),
+                ..Bookmark::new(PathBuf::from("/c"))
+            },
+            Bookmark {
+                last_used: Some(9),
+                ..Bookmark::new(PathBuf::from("/b"))
+            },
+            Bookmark {
+                pinned: true,
+                ..Bookmark::new(PathBuf::from("/d"))
+            },
+            Bookmark::new(PathBuf::from("/a")),
+        ];
+
+        sort(&mut bookmarks, Sort::Alpha, 10);
+        assert_eq!(paths(&bookmarks), ["/d", "/a", "/b", "/c"]);
+
+        sort(&mut bookmarks, Sort::Recent, 10);
+        assert_eq!(paths(&bookmarks), ["/d", "/b", "/c", "/a"]);
+    }
+
+    #[test]
+    fn move_clamps_to_end() {
+        let mut bookmarks: Vec<_> = ["/a", "/b", "/c"]
+            .into_iter()
+            .map(|p| Bookmark::new(PathBuf::from(p)))
+            .collect();
+
+        move_to(&mut bookmarks, 2, 0);
+        assert_eq!(paths(&bookmarks), ["/c", "/a", "/b"]);
+
+        move_to(&mut bookmarks, 0, 10);
+        assert_eq!(paths(&bookmarks), ["/a", "/b", "/c"]);
+    }
+}
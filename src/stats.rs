@@ -0,0 +1,163 @@
+//! Summary of the store printed by `pathmarks stats`.
+
+use std::cmp::Reverse;
+
+use serde::Serialize;
+
+use crate::age::format_age;
+use crate::remote::is_remote;
+use crate::store::Bookmark;
+
+/// How many bookmarks are shown as most and least used.
+const SHOWN: usize = 5;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Stats {
+    pub bookmarks: usize,
+    /// Bookmarks whose directory does not exist.
+    pub missing: usize,
+    /// Size of the store file in bytes.
+    pub store_size: u64,
+    pub most_used: Vec<Usage>,
+    pub least_used: Vec<Usage>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Usage {
+    pub path: String,
+    pub visits: u64,
+    /// Seconds since the unix epoch.
+    pub last_visit: Option<u64>,
+}
+
+impl Usage {
+    fn new(bookmark: &Bookmark) -> Self {
+        Self {
+            path: bookmark.path.to_string_lossy().into_owned(),
+            visits: bookmark.visits.unwrap_or_default(),
+            last_visit: bookmark.last_used,
+        }
+    }
+}
+
+pub fn stats(bookmarks: &[Bookmark], store_size: u64) -> Stats {
+    let mut by_use: Vec<_> = bookmarks.iter().collect();
+    by_use.sort_by_key(|b| (Reverse(b.visits.unwrap_or_default()), Reverse(b.last_used)));
+
+    let least_used = by_use.iter().rev().take(SHOWN).map(|b| Usage::new(b));
+
+    Stats {
+        bookmarks: bookmarks.len(),
+        missing: bookmarks
+            .iter()
+            .filter(|b| !is_remote(&b.path) && !b.path.exists())
+            .count(),
+        store_size,
+        most_used: by_use.iter().take(SHOWN).map(|b| Usage::new(b)).collect(),
+        least_used: least_used.collect(),
+    }
+}
+
+impl Stats {
+    pub fn plain(&self, now: u64) -> String {
+        let mut out = vec![
+            format!("Bookmarks:  {}", self.bookmarks),
+            format!("Missing:    {}", self.missing),
+            format!("Store size: {} bytes", self.store_size),
+        ];
+
+        let usage = |usage: &Usage| {
+            let last = usage.last_visit.map_or("never".to_string(), |t| {
+                format!("{} ago", format_age(now.saturating_sub(t)))
+            });
+            format!("  {:>5}  {last:>9}  {}", usage.visits, usage.path)
+        };
+        out.push("Most used:".to_string());
+        out.extend(self.most_used.iter().map(usage));
+        out.push("Least used:".to_string());
+        out.extend(self.least_used.iter().map(usage));
+
+        out.join("\n")
+    }
+
+    /// Records of `stats --porcelain`, without the line ending.
+    pub fn porcelain(&self) -> Vec<String> {
+        let mut out = vec![
+            format!("bookmarks\t{}", self.bookmarks),
+            format!("missing\t{}", self.missing),
+            format!("store_size\t{}", self.store_size),
+        ];
+
+        let usage = |kind: &str, usage: &Usage| {
+            let last = usage.last_visit.map(|t| t.to_string()).unwrap_or_default();
+            format!("{kind}\t{}\t{}\t{last}", usage.path, usage.visits)
+        };
+        out.extend(self.most_used.iter().map(|u| usage("most_used", u)));
+        out.extend(self.least_used.iter().map(|u| usage("least_used", u)));
+
+        out
+    }
+
+    pub fn json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("stats serialize to json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn most_and_least_used() {
+        let temp = tempfile::tempdir().unwrap();
+        let bookmarks = [
+            Bookmark {
+                visits: Some(3),
+                last_used: Some(100),
+                ..Bookmark::new(temp.path().to_path_buf())
+            },
+            Bookmark::new(PathBuf::from("/nonexistent/pathmarks")),
+            Bookmark {
+                visits: Some(9),
+                ..Bookmark::new(PathBuf::from("/nonexistent/often"))
+            },
+        ];
+
+        let stats = stats(&bookmarks, 42);
+
+        assert_eq!(stats.bookmarks, 3);
+        assert_eq!(stats.missing, 2);
+        assert_eq!(stats.most_used[0].path, "/nonexistent/often");
+        assert_eq!(stats.least_used[0].path, "/nonexistent/pathmarks");
+        assert_eq!(stats.most_used[1].last_visit, Some(100));
+    }
+
+    #[test]
+    fn porcelain_is_tab_separated() {
+        let bookmarks = [
+            Bookmark {
+                visits: Some(3),
+                last_used: Some(100),
+                ..Bookmark::new(PathBuf::from("/nonexistent/often"))
+            },
+            Bookmark::new(PathBuf::from("/nonexistent/never")),
+        ];
+
+        let out = stats(&bookmarks, 42).porcelain();
+
+        assert_eq!(
+            out,
+            [
+                "bookmarks\t2",
+                "missing\t2",
+                "store_size\t42",
+                "most_used\t/nonexistent/often\t3\t100",
+                "most_used\t/nonexistent/never\t0\t",
+                "least_used\t/nonexistent/never\t0\t",
+                "least_used\t/nonexistent/often\t3\t100",
+            ]
+        );
+    }
+}
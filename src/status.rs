@@ -0,0 +1,49 @@
+//! `pathmarks status`, a short indicator for the prompt when the current
+//! directory is bookmarked.
+
+use crate::store::Bookmark;
+
+/// The default `--format`.
+pub const FORMAT: &str = "★ {name}";
+
+/// `format` with `{name}` replaced by the name of `bookmark`, or else the
+/// name of its directory, `{icon}` by its icon and `{path}` by its path.
+pub fn status(format: &str, bookmark: &Bookmark) -> String {
+    let name = match &bookmark.name {
+        Some(name) => name.clone(),
+        None => bookmark
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+
+    format
+        .replace("{name}", &name)
+        .replace("{icon}", bookmark.icon.as_deref().unwrap_or_default())
+        .replace("{path}", &bookmark.path.to_string_lossy())
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn fills_in_the_bookmark() {
+        let bookmark = Bookmark::new(PathBuf::from("/src/pathmarks"));
+        assert_eq!(status(FORMAT, &bookmark), "★ pathmarks");
+
+        let named = Bookmark {
+            name: Some("pm".to_string()),
+            ..bookmark
+        };
+        assert_eq!(
+            status("{icon} {name} in {path}", &named),
+            "pm in /src/pathmarks"
+        );
+    }
+}
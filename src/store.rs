@@ -0,0 +1,521 @@
+//! Reading and writing the bookmarks file.
+//!
+//! Each line holds one bookmark: the absolute path, optionally followed by
+//! tab-separated `key=value` metadata fields. A plain list of paths is
+//! therefore still a valid store.
+//!
+//! The text file is the default [`Store`]; a SQLite database can be used
+//! instead by setting `backend = "sqlite"` in the config.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::command::{filter, from_line};
+use crate::error::{AppError, AppResult};
+
+pub mod daemon;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod toml_file;
+#[cfg(all(unix, feature = "watch"))]
+mod watch;
+
+/// Where bookmarks are persisted.
+pub trait Store {
+    /// The file holding the bookmarks.
+    fn file(&self) -> &Path;
+    /// All bookmarks, in their stored order.
+    fn read(&self) -> AppResult<Vec<Bookmark>>;
+    /// Replaces all bookmarks with `bookmarks`.
+    fn write(&self, bookmarks: &[Bookmark]) -> AppResult<()>;
+
+    /// Bookmarks whose directory is known to be gone, from a daemon watching
+    /// them. Empty when nothing watches.
+    fn dead(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Waits for exclusive access to the store. Changes should read the
+    /// bookmarks after taking the lock, so writes of other processes are not
+    /// lost.
+    fn lock(&self) -> AppResult<StoreLock> {
+        StoreLock::acquire(self.file())
+    }
+}
+
+/// An advisory lock on a lock file next to the store, released when
+/// dropped.
+pub struct StoreLock {
+    _file: File,
+}
+
+impl StoreLock {
+    pub fn acquire(file: &Path) -> AppResult<Self> {
+        let lock_file = file.with_extension("lock");
+        if let Some(parent) = lock_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let lock = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_file)?;
+        lock.lock()?;
+        Ok(Self { _file: lock })
+    }
+}
+
+/// The format bookmarks are stored in, `backend` in the config.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Text,
+    Sqlite,
+    Toml,
+}
+
+/// Opens the store of `backend`. `text_file` is the bookmarks text file, the
+/// other backends use the same file name with their own extension.
+pub fn open_store(
+    backend: Backend,
+    text_file: PathBuf,
+    encryption: Option<Encryption>,
+) -> AppResult<Box<dyn Store>> {
+    match backend {
+        Backend::Text => Ok(Box::new(TextStore {
+            file: text_file,
+            encryption,
+        })),
+        _ if encryption.is_some() => Err(AppError::EncryptionUnsupported),
+        Backend::Toml => Ok(Box::new(toml_file::TomlStore {
+            file: text_file.with_extension("toml"),
+            text_file,
+        })),
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite => Ok(Box::new(sqlite::SqliteStore::open(
+            text_file.with_extension("sqlite3"),
+        )?)),
+        #[cfg(not(feature = "sqlite"))]
+        Backend::Sqlite => Err(AppError::SqliteUnsupported),
+    }
+}
+
+/// Commands encrypting and decrypting the store, each reading from stdin and
+/// writing to stdout.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Encryption {
+    pub encrypt: String,
+    pub decrypt: String,
+}
+
+/// The user's bookmarks text file, optionally encrypted at rest.
+pub struct TextStore {
+    pub file: PathBuf,
+    pub encryption: Option<Encryption>,
+}
+
+impl Store for TextStore {
+    fn file(&self) -> &Path {
+        &self.file
+    }
+
+    fn read(&self) -> AppResult<Vec<Bookmark>> {
+        decode(fs::read(&self.file)?, self.encryption.as_ref())
+    }
+
+    fn write(&self, bookmarks: &[Bookmark]) -> AppResult<()> {
+        let content = format_bookmarks(bookmarks).into_bytes();
+
+        let content = match &self.encryption {
+            Some(encryption) => filter(from_line(&encryption.encrypt)?, &content)?,
+            None => content,
+        };
+
+        write_atomic(&content, &self.file)
+    }
+}
+
+/// The bookmarks of a text store file holding `content`.
+pub fn decode(content: Vec<u8>, encryption: Option<&Encryption>) -> AppResult<Vec<Bookmark>> {
+    let content = match encryption {
+        // A new store starts out as an empty, unencrypted file.
+        Some(encryption) if !content.is_empty() => {
+            filter(from_line(&encryption.decrypt)?, &content)?
+        }
+        _ => content,
+    };
+
+    Ok(parse_bookmarks(&String::from_utf8_lossy(&content)))
+}
+
+/// A bookmarked directory, or file, with its metadata.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Bookmark {
+    /// Stored as saved, possibly starting with `~` or a variable.
+    pub path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opener: Option<String>,
+    /// When the bookmark was last jumped to, in seconds since the unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<u64>,
+    /// How often the directory was visited, which ranks it by frecency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visits: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// When the bookmark was saved, in seconds since the unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Quick slot from 1 to 9, jumped to with `goto`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u8>,
+}
+
+impl Bookmark {
+    /// A bookmark of `path` without metadata.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            ..Default::default()
+        }
+    }
+
+    /// Fills metadata missing on `self` from `other`.
+    pub fn merge(&mut self, other: Bookmark) {
+        self.name = self.name.take().or(other.name);
+        self.icon = self.icon.take().or(other.icon);
+        self.opener = self.opener.take().or(other.opener);
+        self.last_used = self.last_used.max(other.last_used);
+        self.visits = self.visits.max(other.visits);
+        self.created_at = match (self.created_at, other.created_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.note = self.note.take().or(other.note);
+        self.pinned |= other.pinned;
+        self.slot = self.slot.or(other.slot);
+        for tag in other.tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+    }
+
+    fn parse(line: &str) -> Self {
+        let mut fields = line.split('\t');
+        let mut bookmark = Self::new(PathBuf::from(fields.next().unwrap_or_default()));
+
+        for field in fields {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+
+            match key {
+                "name" => bookmark.name = Some(value.to_string()),
+                "icon" => bookmark.icon = Some(value.to_string()),
+                "opener" => bookmark.opener = Some(value.to_string()),
+                "last_used" => bookmark.last_used = value.parse().ok(),
+                "visits" => bookmark.visits = value.parse().ok(),
+                "tags" => bookmark.tags = value.split(',').map(String::from).collect(),
+                "created_at" => bookmark.created_at = value.parse().ok(),
+                "note" => bookmark.note = Some(value.to_string()),
+                "pinned" => bookmark.pinned = value == "true",
+                "slot" => bookmark.slot = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        bookmark
+    }
+
+    /// The first field of a bookmark line that `Bookmark::parse` would
+    /// drop or misread.
+    pub fn malformed_field(line: &str) -> Option<&str> {
+        line.split('\t').skip(1).find(|field| {
+            let Some((key, value)) = field.split_once('=') else {
+                return true;
+            };
+
+            match key {
+                "name" | "icon" | "opener" | "tags" | "note" => false,
+                "last_used" | "visits" | "created_at" => value.parse::<u64>().is_err(),
+                "pinned" => value != "true",
+                "slot" => value.parse::<u8>().is_err(),
+                _ => true,
+            }
+        })
+    }
+
+    fn line(&self) -> String {
+        let mut line = self.path.display().to_string();
+
+        if let Some(name) = &self.name {
+            line.push_str(&format!("\tname={name}"));
+        }
+        if let Some(icon) = &self.icon {
+            line.push_str(&format!("\ticon={icon}"));
+        }
+        if let Some(opener) = &self.opener {
+            line.push_str(&format!("\topener={opener}"));
+        }
+        if let Some(last_used) = self.last_used {
+            line.push_str(&format!("\tlast_used={last_used}"));
+        }
+        if let Some(visits) = self.visits {
+            line.push_str(&format!("\tvisits={visits}"));
+        }
+        if !self.tags.is_empty() {
+            line.push_str(&format!("\ttags={}", self.tags.join(",")));
+        }
+        if let Some(created_at) = self.created_at {
+            line.push_str(&format!("\tcreated_at={created_at}"));
+        }
+        if let Some(note) = &self.note {
+            line.push_str(&format!("\tnote={note}"));
+        }
+        if self.pinned {
+            line.push_str("\tpinned=true");
+        }
+        if let Some(slot) = self.slot {
+            line.push_str(&format!("\tslot={slot}"));
+        }
+
+        line
+    }
+}
+
+/// Returns `name`, suffixed with `-2`, `-3`, ... if another bookmark
+/// already uses it.
+pub fn unique_name(bookmarks: &[Bookmark], name: &str) -> String {
+    let taken = |candidate: &str| {
+        bookmarks
+            .iter()
+            .any(|b| b.name.as_deref() == Some(candidate))
+    };
+
+    if !taken(name) {
+        return name.to_string();
+    }
+
+    (2..)
+        .map(|n| format!("{name}-{n}"))
+        .find(|candidate| !taken(candidate))
+        .expect("suffixes are unbounded")
+}
+
+/// Tags are single words without commas.
+pub fn validate_tag(tag: &str) -> AppResult<()> {
+    if tag.is_empty() || tag.contains(',') || tag.contains(char::is_whitespace) {
+        return Err(AppError::InvalidTag(tag.to_string()));
+    }
+    Ok(())
+}
+
+/// Metadata values must fit on their line.
+pub fn validate_value(value: &str) -> AppResult<()> {
+    if value.contains(['\t', '\n', '\r']) {
+        return Err(AppError::InvalidMetadata);
+    }
+    Ok(())
+}
+
+/// Reads a plain bookmarks text file.
+pub fn read_bookmarks(file: &Path) -> AppResult<Vec<Bookmark>> {
+    Ok(parse_bookmarks(&fs::read_to_string(file)?))
+}
+
+/// Writes a plain bookmarks text file, replacing it at once.
+pub fn write_bookmarks(bookmarks: &[Bookmark], file: &Path) -> AppResult<()> {
+    write_atomic(format_bookmarks(bookmarks).as_bytes(), file)
+}
+
+/// The bookmarks of the lines of a bookmarks text file.
+pub fn parse_bookmarks(content: &str) -> Vec<Bookmark> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(Bookmark::parse)
+        .collect()
+}
+
+/// The bookmarks text file of `bookmarks`, one line each.
+pub fn format_bookmarks(bookmarks: &[Bookmark]) -> String {
+    bookmarks.iter().map(|b| b.line() + "\n").collect()
+}
+
+/// Replaces `file` with `content` through a temporary file in the same
+/// directory, so a crash leaves either the old or the new content behind.
+/// The permissions of an existing file are kept.
+pub fn write_atomic(content: &[u8], file: &Path) -> AppResult<()> {
+    let parent = match file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+
+    let mut tmp_name = file.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = file.with_file_name(tmp_name);
+
+    let replace = || -> io::Result<()> {
+        let mut out = File::create(&tmp)?;
+        // Before writing, so a private file is never readable by others.
+        if let Ok(metadata) = fs::metadata(file) {
+            out.set_permissions(metadata.permissions())?;
+        }
+        out.write_all(content)?;
+        out.sync_all()?;
+        fs::rename(&tmp, file)
+    };
+    if let Err(err) = replace() {
+        let _ = fs::remove_file(&tmp);
+        return Err(err.into());
+    }
+
+    // The rename is only durable once the directory is on disk too. Windows
+    // cannot open directories as files.
+    #[cfg(unix)]
+    File::open(parent)?.sync_all()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_paths_are_bookmarks() {
+        assert_eq!(
+            Bookmark::parse("/tmp/a"),
+            Bookmark::new(PathBuf::from("/tmp/a"))
+        );
+    }
+
+    #[test]
+    fn metadata_roundtrip() {
+        let bookmark = Bookmark {
+            path: PathBuf::from("/tmp/with space"),
+            name: Some("work".to_string()),
+            icon: Some("🚀".to_string()),
+            opener: Some("code --new-window".to_string()),
+            last_used: Some(1_700_000_000),
+            visits: Some(12),
+            tags: vec!["work".to_string(), "rust".to_string()],
+            created_at: Some(1_600_000_000),
+            note: Some("deploys from here".to_string()),
+            pinned: true,
+            slot: Some(4),
+        };
+
+        assert_eq!(Bookmark::parse(&bookmark.line()), bookmark);
+    }
+
+    #[test]
+    fn unique_name_suffixes_collisions() {
+        let named = |name: &str| Bookmark {
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+        let bookmarks = [named("org/repo"), named("org/repo-2"), named("other")];
+
+        assert_eq!(unique_name(&bookmarks, "new"), "new");
+        assert_eq!(unique_name(&bookmarks, "org/repo"), "org/repo-3");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn encrypted_store_roundtrip() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = TextStore {
+            file: temp.path().join("bookmarks.txt"),
+            encryption: Some(Encryption {
+                encrypt: "rev".to_string(),
+                decrypt: "rev".to_string(),
+            }),
+        };
+        fs::write(&store.file, "").unwrap();
+        assert!(store.read().unwrap().is_empty());
+
+        let bookmarks = [Bookmark::new(PathBuf::from("/secret/client"))];
+        store.write(&bookmarks).unwrap();
+
+        assert_eq!(fs::read_to_string(&store.file).unwrap(), "tneilc/terces/\n");
+        assert_eq!(store.read().unwrap(), bookmarks);
+    }
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = TextStore {
+            file: temp.path().join("bookmarks.txt"),
+            encryption: None,
+        };
+        let other = || {
+            File::options()
+                .write(true)
+                .open(temp.path().join("bookmarks.lock"))
+                .unwrap()
+                .try_lock()
+                .is_ok()
+        };
+
+        let lock = store.lock().unwrap();
+        assert!(!other());
+        drop(lock);
+        assert!(other());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_write_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.txt");
+        fs::write(&file, "/old\n").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_atomic(b"/new\n", &file).unwrap();
+
+        assert_eq!(fs::read_to_string(&file).unwrap(), "/new\n");
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn failed_atomic_write_leaves_nothing_behind() {
+        let temp = tempfile::tempdir().unwrap();
+        // A directory with something in it cannot be replaced by a file.
+        let file = temp.path().join("bookmarks.txt");
+        fs::create_dir_all(file.join("taken")).unwrap();
+
+        assert!(write_atomic(b"/new\n", &file).is_err());
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored() {
+        let bookmark = Bookmark::parse("/tmp/a\tcolor=red\ticon=x");
+
+        assert_eq!(bookmark.icon.as_deref(), Some("x"));
+    }
+}
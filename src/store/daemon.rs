@@ -0,0 +1,363 @@
+//! `pathmarks daemon`, keeping the bookmarks in memory behind a Unix socket
+//! in a private directory next to the store. Other commands go through it
+//! while it runs and read the store themselves otherwise.
+//!
+//! A request is `read`, `dead`, or `write` followed by the bookmarks as
+//! JSON, after which the client closes its side. The answer is `ok` followed
+//! by the bookmarks of a read as JSON or the dead paths, one per line, or
+//! `error` followed by a message. Bookmarks go as JSON rows so clients skip
+//! parsing the text format, and the daemon keeps the rows it sends until
+//! the store changes.
+
+use std::path::{Path, PathBuf};
+
+use super::Store;
+
+/// The socket of the daemon serving `store_file`, in a directory only the
+/// user can enter.
+pub fn socket_path(store_file: &Path) -> PathBuf {
+    store_file.with_extension("daemon").join("socket")
+}
+
+#[cfg(unix)]
+pub use unix::{connect, serve};
+
+#[cfg(not(unix))]
+pub fn connect(store: Box<dyn Store>) -> Box<dyn Store> {
+    store
+}
+
+#[cfg(not(unix))]
+pub fn serve(
+    _store: &dyn Store,
+    _watch: bool,
+    _local: &dyn Fn(&Path) -> PathBuf,
+) -> crate::error::AppResult<()> {
+    Err(crate::error::AppError::DaemonUnsupported)
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::fs::{self, DirBuilder};
+    use std::io::{self, Read, Write};
+    use std::net::Shutdown;
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{self, Sender};
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    use tracing::{info, warn};
+
+    use super::{Store, socket_path};
+    use crate::error::{AppError, AppResult};
+    use crate::store::Bookmark;
+    #[cfg(feature = "watch")]
+    use crate::store::watch::Watch;
+
+    /// How long either side waits for the other.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// `store` read and written through the daemon when one is running for
+    /// it.
+    pub fn connect(store: Box<dyn Store>) -> Box<dyn Store> {
+        let socket = socket_path(store.file());
+        if UnixStream::connect(&socket).is_err() {
+            return store;
+        }
+        Box::new(DaemonStore {
+            direct: store,
+            socket,
+        })
+    }
+
+    struct DaemonStore {
+        /// Used when the daemon stops answering.
+        direct: Box<dyn Store>,
+        socket: PathBuf,
+    }
+
+    impl DaemonStore {
+        /// The answer of the daemon, or the error message it sent back.
+        fn request(&self, request: &str) -> io::Result<Result<String, String>> {
+            let mut stream = UnixStream::connect(&self.socket)?;
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            stream.write_all(request.as_bytes())?;
+            stream.shutdown(Shutdown::Write)?;
+
+            let mut answer = String::new();
+            stream.read_to_string(&mut answer)?;
+            match answer.split_once('\n') {
+                Some(("ok", body)) => Ok(Ok(body.to_string())),
+                Some(("error", message)) => Ok(Err(message.to_string())),
+                _ => Err(io::Error::new(io::ErrorKind::InvalidData, answer)),
+            }
+        }
+    }
+
+    impl Store for DaemonStore {
+        fn file(&self) -> &Path {
+            self.direct.file()
+        }
+
+        fn read(&self) -> AppResult<Vec<Bookmark>> {
+            match self.request("read\n") {
+                Ok(Ok(body)) => Ok(serde_json::from_str(&body)?),
+                Ok(Err(message)) => Err(AppError::Daemon(message)),
+                Err(err) => {
+                    warn!("The daemon did not answer, reading the store directly: {err}");
+                    self.direct.read()
+                }
+            }
+        }
+
+        fn write(&self, bookmarks: &[Bookmark]) -> AppResult<()> {
+            let request = format!("write\n{}", serde_json::to_string(bookmarks)?);
+            match self.request(&request) {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(message)) => Err(AppError::Daemon(message)),
+                Err(err) => {
+                    warn!("The daemon did not answer, writing the store directly: {err}");
+                    self.direct.write(bookmarks)
+                }
+            }
+        }
+
+        fn dead(&self) -> Vec<PathBuf> {
+            match self.request("dead\n") {
+                Ok(Ok(body)) => body.lines().map(PathBuf::from).collect(),
+                _ => Vec::new(),
+            }
+        }
+    }
+
+    /// Answers requests for `store` until the process is stopped, watching
+    /// the bookmarked directories when `watch` where `local` says they are.
+    pub fn serve(
+        store: &dyn Store,
+        watch: bool,
+        local: &dyn Fn(&Path) -> PathBuf,
+    ) -> AppResult<()> {
+        #[cfg(not(feature = "watch"))]
+        if watch {
+            return Err(AppError::WatchUnsupported);
+        }
+
+        let socket = socket_path(store.file());
+        if UnixStream::connect(&socket).is_ok() {
+            return Err(AppError::DaemonRunning(socket));
+        }
+        // Other users cannot connect to a socket in a directory they cannot
+        // enter, from the moment it is bound.
+        if let Some(dir) = socket.parent() {
+            DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        // Left behind by a daemon that was killed.
+        if socket.exists() {
+            fs::remove_file(&socket)?;
+        }
+        let listener = UnixListener::bind(&socket)?;
+        info!("Serving {} on {}", store.file().display(), socket.display());
+
+        let mut cache = Cache {
+            store,
+            bookmarks: Vec::new(),
+            rows: None,
+            stamp: None,
+            #[cfg(feature = "watch")]
+            watch: watch.then(Watch::new).transpose()?,
+            #[cfg(feature = "watch")]
+            local,
+        };
+        #[cfg(not(feature = "watch"))]
+        let _ = local;
+
+        // Every client is read from and written to on its own thread, so a
+        // slow one does not hold up the others. Only the answers are made
+        // here, one at a time.
+        let (requests, received) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let requests = requests.clone();
+                thread::spawn(move || {
+                    if let Err(err) = stream.and_then(|mut stream| exchange(&mut stream, requests))
+                    {
+                        warn!("Could not answer a request: {err}");
+                    }
+                });
+            }
+        });
+        for (request, answer) in received {
+            let _ = answer.send(cache.answer(&request));
+        }
+        Ok(())
+    }
+
+    /// Reads the request of a client and sends it the answer made for it.
+    fn exchange(
+        stream: &mut UnixStream,
+        requests: Sender<(String, Sender<String>)>,
+    ) -> io::Result<()> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut request = String::new();
+        stream.read_to_string(&mut request)?;
+        // Commands connect without a request to see if the daemon runs.
+        if request.is_empty() {
+            return Ok(());
+        }
+
+        let (answer, answered) = mpsc::channel();
+        let stopped = || io::Error::other("the daemon stopped");
+        requests.send((request, answer)).map_err(|_| stopped())?;
+        let answer = answered.recv().map_err(|_| stopped())?;
+        stream.write_all(answer.as_bytes())
+    }
+
+    /// The bookmarks of the store, read again when its file changes.
+    struct Cache<'a> {
+        store: &'a dyn Store,
+        bookmarks: Vec<Bookmark>,
+        /// `bookmarks` as sent to clients, made for the first read after a
+        /// change.
+        rows: Option<String>,
+        stamp: Option<(SystemTime, u64)>,
+        #[cfg(feature = "watch")]
+        watch: Option<Watch>,
+        /// Where a stored path is on this machine, for watching it.
+        #[cfg(feature = "watch")]
+        local: &'a dyn Fn(&Path) -> PathBuf,
+    }
+
+    impl Cache<'_> {
+        fn answer(&mut self, request: &str) -> String {
+            match self.handle(request) {
+                Ok(body) => format!("ok\n{body}"),
+                Err(err) => format!("error\n{err}"),
+            }
+        }
+
+        fn handle(&mut self, request: &str) -> AppResult<String> {
+            match request.split_once('\n') {
+                Some(("read", _)) => {
+                    self.bookmarks()?;
+                    if self.rows.is_none() {
+                        self.rows = Some(serde_json::to_string(&self.bookmarks)?);
+                    }
+                    Ok(self.rows.clone().unwrap_or_default())
+                }
+                Some(("dead", _)) => {
+                    self.bookmarks()?;
+                    let dead: Vec<_> = self
+                        .dead()
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect();
+                    Ok(dead.join("\n"))
+                }
+                Some(("write", body)) => {
+                    let bookmarks: Vec<Bookmark> = serde_json::from_str(body)?;
+                    self.store.write(&bookmarks)?;
+                    self.bookmarks = bookmarks;
+                    self.rows = None;
+                    self.stamp = stamp(self.store.file());
+                    self.watch_bookmarks();
+                    Ok(String::new())
+                }
+                _ => Err(AppError::Daemon(format!("unknown request {request:?}"))),
+            }
+        }
+
+        fn bookmarks(&mut self) -> AppResult<&[Bookmark]> {
+            let stamp = stamp(self.store.file());
+            if stamp.is_none() || stamp != self.stamp {
+                self.bookmarks = self.store.read()?;
+                self.rows = None;
+                self.stamp = stamp;
+                self.watch_bookmarks();
+            }
+            Ok(&self.bookmarks)
+        }
+
+        #[cfg(feature = "watch")]
+        fn watch_bookmarks(&mut self) {
+            if let Some(watch) = &mut self.watch {
+                let local = self.local;
+                watch.update(
+                    self.bookmarks
+                        .iter()
+                        .map(|b| (b.path.clone(), local(&b.path))),
+                );
+            }
+        }
+
+        #[cfg(not(feature = "watch"))]
+        fn watch_bookmarks(&mut self) {}
+
+        #[cfg(feature = "watch")]
+        fn dead(&self) -> Vec<PathBuf> {
+            self.watch.as_ref().map_or_else(Vec::new, Watch::dead)
+        }
+
+        #[cfg(not(feature = "watch"))]
+        fn dead(&self) -> Vec<PathBuf> {
+            Vec::new()
+        }
+    }
+
+    /// When `file` was changed and how long it is, to notice other writers.
+    fn stamp(file: &Path) -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(file).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::thread;
+
+        use super::*;
+        use crate::store::TextStore;
+
+        fn text_store(file: &Path) -> Box<dyn Store> {
+            Box::new(TextStore {
+                file: file.to_path_buf(),
+                encryption: None,
+            })
+        }
+
+        #[test]
+        fn reads_and_writes_through_the_daemon() {
+            let temp = tempfile::tempdir().unwrap();
+            let file = temp.path().join("bookmarks.txt");
+            fs::write(&file, "/a\n").unwrap();
+
+            let served = file.clone();
+            thread::spawn(move || serve(text_store(&served).as_ref(), false, &Path::to_path_buf));
+            while UnixStream::connect(socket_path(&file)).is_err() {
+                thread::sleep(Duration::from_millis(10));
+            }
+
+            let store = connect(text_store(&file));
+            assert_eq!(store.read().unwrap()[0].path, Path::new("/a"));
+
+            store.write(&[Bookmark::new(PathBuf::from("/b"))]).unwrap();
+            assert_eq!(fs::read_to_string(&file).unwrap(), "/b\n");
+
+            // Writes of others are picked up.
+            fs::write(&file, "/c\n/d\n").unwrap();
+            assert_eq!(store.read().unwrap().len(), 2);
+
+            let dir = socket_path(&file).parent().unwrap().to_path_buf();
+            let mode = fs::metadata(dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+
+            assert!(matches!(
+                serve(text_store(&file).as_ref(), false, &Path::to_path_buf),
+                Err(AppError::DaemonRunning(_))
+            ));
+        }
+    }
+}
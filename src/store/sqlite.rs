@@ -0,0 +1,166 @@
+//! Bookmarks in a SQLite database, one row per bookmark ordered by
+//! `position`. The schema version is kept in `PRAGMA user_version`.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, params};
+
+use super::{Bookmark, Store};
+use crate::error::AppResult;
+
+/// Run in order on databases older than their index + 1.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS bookmarks (
+        position INTEGER PRIMARY KEY,
+        path TEXT NOT NULL,
+        name TEXT,
+        icon TEXT,
+        opener TEXT,
+        last_used INTEGER,
+        visits INTEGER,
+        tags TEXT NOT NULL DEFAULT ''
+    )",
+    "ALTER TABLE bookmarks ADD COLUMN created_at INTEGER;
+     ALTER TABLE bookmarks ADD COLUMN note TEXT;
+     ALTER TABLE bookmarks ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;",
+    "ALTER TABLE bookmarks ADD COLUMN slot INTEGER",
+];
+
+pub struct SqliteStore {
+    file: PathBuf,
+    connection: Connection,
+}
+
+impl SqliteStore {
+    pub fn open(file: PathBuf) -> AppResult<Self> {
+        let connection = Connection::open(&file)?;
+
+        let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            connection.execute_batch(migration)?;
+            connection.pragma_update(None, "user_version", i + 1)?;
+        }
+
+        Ok(Self { file, connection })
+    }
+}
+
+impl Store for SqliteStore {
+    fn file(&self) -> &Path {
+        &self.file
+    }
+
+    fn read(&self) -> AppResult<Vec<Bookmark>> {
+        let mut statement = self.connection.prepare(
+            "SELECT path, name, icon, opener, last_used, visits, tags, created_at, note, pinned,
+                    slot
+             FROM bookmarks ORDER BY position",
+        )?;
+
+        let rows = statement.query_map([], |row| {
+            let tags: String = row.get(6)?;
+            Ok(Bookmark {
+                path: PathBuf::from(row.get::<_, String>(0)?),
+                name: row.get(1)?,
+                icon: row.get(2)?,
+                opener: row.get(3)?,
+                last_used: row.get(4)?,
+                visits: row.get(5)?,
+                tags: tags
+                    .split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(String::from)
+                    .collect(),
+                created_at: row.get(7)?,
+                note: row.get(8)?,
+                pinned: row.get(9)?,
+                slot: row.get(10)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn write(&self, bookmarks: &[Bookmark]) -> AppResult<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute("DELETE FROM bookmarks", [])?;
+
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO bookmarks
+                 (position, path, name, icon, opener, last_used, visits, tags,
+                  created_at, note, pinned, slot)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            )?;
+            for (position, b) in bookmarks.iter().enumerate() {
+                insert.execute(params![
+                    position,
+                    b.path.to_string_lossy(),
+                    b.name,
+                    b.icon,
+                    b.opener,
+                    b.last_used,
+                    b.visits,
+                    b.tags.join(","),
+                    b.created_at,
+                    b.note,
+                    b.pinned,
+                    b.slot,
+                ])?;
+            }
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = SqliteStore::open(temp.path().join("bookmarks.sqlite3")).unwrap();
+        assert!(store.read().unwrap().is_empty());
+
+        let bookmarks = [
+            Bookmark {
+                path: PathBuf::from("/src/api"),
+                name: Some("api".to_string()),
+                last_used: Some(1_700_000_000),
+                visits: Some(3),
+                tags: vec!["work".to_string(), "rust".to_string()],
+                note: Some("deploys from here".to_string()),
+                pinned: true,
+                slot: Some(2),
+                ..Default::default()
+            },
+            Bookmark::new(PathBuf::from("/home/me")),
+        ];
+        store.write(&bookmarks).unwrap();
+        store.write(&bookmarks).unwrap();
+
+        assert_eq!(store.read().unwrap(), bookmarks);
+    }
+
+    #[test]
+    fn migrates_old_schema() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("bookmarks.sqlite3");
+        Connection::open(&file)
+            .unwrap()
+            .execute_batch(MIGRATIONS[0])
+            .unwrap();
+
+        let store = SqliteStore::open(file).unwrap();
+        let bookmarks = [Bookmark {
+            pinned: true,
+            ..Bookmark::new(PathBuf::from("/src"))
+        }];
+        store.write(&bookmarks).unwrap();
+
+        assert_eq!(store.read().unwrap(), bookmarks);
+    }
+}
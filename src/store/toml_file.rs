@@ -0,0 +1,115 @@
+//! Bookmarks in a versioned TOML file.
+//!
+//! ```toml
+//! version = 1
+//!
+//! [[bookmarks]]
+//! path = "/home/me/src/pathmarks"
+//! name = "sofusa/pathmarks"
+//! tags = ["rust"]
+//! ```
+//!
+//! When the file does not exist yet, the text store next to it is read and
+//! upgraded into it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Bookmark, Store, read_bookmarks, write_atomic};
+use crate::error::{AppError, AppResult};
+
+const VERSION: u32 = 1;
+
+#[derive(Deserialize, Serialize)]
+struct TomlBookmarks {
+    version: u32,
+    #[serde(default)]
+    bookmarks: Vec<Bookmark>,
+}
+
+pub struct TomlStore {
+    pub file: PathBuf,
+    /// The text store upgraded on first read.
+    pub text_file: PathBuf,
+}
+
+impl Store for TomlStore {
+    fn file(&self) -> &Path {
+        &self.file
+    }
+
+    fn read(&self) -> AppResult<Vec<Bookmark>> {
+        if !self.file.exists() {
+            let bookmarks = match self.text_file.exists() {
+                true => read_bookmarks(&self.text_file)?,
+                false => Vec::new(),
+            };
+            self.write(&bookmarks)?;
+            return Ok(bookmarks);
+        }
+
+        let stored: TomlBookmarks = ::toml::from_str(&fs::read_to_string(&self.file)?)?;
+        if stored.version > VERSION {
+            return Err(AppError::UnsupportedStoreVersion(stored.version));
+        }
+
+        Ok(stored.bookmarks)
+    }
+
+    fn write(&self, bookmarks: &[Bookmark]) -> AppResult<()> {
+        let stored = TomlBookmarks {
+            version: VERSION,
+            bookmarks: bookmarks.to_vec(),
+        };
+        let content = ::toml::to_string(&stored).expect("bookmarks serialize to toml");
+
+        write_atomic(content.as_bytes(), &self.file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::write_bookmarks;
+
+    #[test]
+    fn upgrades_text_store() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = TomlStore {
+            file: temp.path().join("bookmarks.toml"),
+            text_file: temp.path().join("bookmarks.txt"),
+        };
+
+        let bookmarks = [
+            Bookmark {
+                name: Some("api".to_string()),
+                note: Some("deploys from here".to_string()),
+                pinned: true,
+                ..Bookmark::new(PathBuf::from("/src/api"))
+            },
+            Bookmark::new(PathBuf::from("/home/me")),
+        ];
+        write_bookmarks(&bookmarks, &store.text_file).unwrap();
+
+        assert_eq!(store.read().unwrap(), bookmarks);
+        assert!(store.file.exists());
+        assert_eq!(store.read().unwrap(), bookmarks);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = TomlStore {
+            file: temp.path().join("bookmarks.toml"),
+            text_file: temp.path().join("bookmarks.txt"),
+        };
+        fs::write(&store.file, "version = 2\n").unwrap();
+
+        assert!(matches!(
+            store.read(),
+            Err(AppError::UnsupportedStoreVersion(2))
+        ));
+    }
+}
@@ -0,0 +1,146 @@
+//! Watching bookmarked directories for `pathmarks daemon --watch`, to know
+//! the moment one is deleted or renamed.
+//!
+//! The parent of every bookmark is watched, as that is where the deletion
+//! or rename of the directory itself shows up. Bookmarks are watched where
+//! they are on this machine and reported dead as they are stored.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{debug, warn};
+
+use crate::remote::is_remote;
+
+#[derive(Default)]
+struct State {
+    /// The stored path of each watched directory.
+    bookmarks: HashMap<PathBuf, PathBuf>,
+    dead: HashSet<PathBuf>,
+}
+
+pub struct Watch {
+    watcher: RecommendedWatcher,
+    state: Arc<Mutex<State>>,
+    parents: HashSet<PathBuf>,
+}
+
+impl Watch {
+    pub fn new() -> notify::Result<Self> {
+        let state = Arc::new(Mutex::new(State::default()));
+        let events = state.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => return warn!("Watching bookmarks failed: {err}"),
+            };
+            let mut state = events.lock().unwrap_or_else(PoisonError::into_inner);
+            for path in event.paths {
+                let Some(stored) = state.bookmarks.get(&path).cloned() else {
+                    continue;
+                };
+                if path.exists() {
+                    state.dead.remove(&stored);
+                } else {
+                    debug!("{} is gone", path.display());
+                    state.dead.insert(stored);
+                }
+            }
+        })?;
+
+        Ok(Self {
+            watcher,
+            state,
+            parents: HashSet::new(),
+        })
+    }
+
+    /// Watches `bookmarks`, pairs of a stored path and where it is on this
+    /// machine, instead of the ones before.
+    pub fn update(&mut self, bookmarks: impl Iterator<Item = (PathBuf, PathBuf)>) {
+        let bookmarks: HashMap<_, _> = bookmarks
+            .filter(|(stored, _)| !is_remote(stored))
+            .map(|(stored, local)| (local, stored))
+            .collect();
+        let parents: HashSet<_> = bookmarks
+            .keys()
+            .filter_map(|path| path.parent())
+            .map(Path::to_path_buf)
+            .collect();
+
+        for parent in self.parents.difference(&parents) {
+            // Fails for parents that could not be watched to begin with.
+            let _ = self.watcher.unwatch(parent);
+        }
+        for parent in parents.difference(&self.parents) {
+            if let Err(err) = self.watcher.watch(parent, RecursiveMode::NonRecursive) {
+                debug!("Cannot watch {}: {err}", parent.display());
+            }
+        }
+        self.parents = parents;
+
+        let dead = bookmarks
+            .iter()
+            .filter(|(local, _)| !local.exists())
+            .map(|(_, stored)| stored.clone())
+            .collect();
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner) = State { bookmarks, dead };
+    }
+
+    /// Bookmarks whose directory is gone, sorted.
+    pub fn dead(&self) -> Vec<PathBuf> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut dead: Vec<_> = state.dead.iter().cloned().collect();
+        dead.sort();
+        dead
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn notices_deleted_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let (kept, deleted) = (root.join("kept"), root.join("deleted"));
+        fs::create_dir(&kept).unwrap();
+        fs::create_dir(&deleted).unwrap();
+
+        let mut watch = Watch::new().unwrap();
+        let missing = root.join("missing");
+        let here = [kept, deleted.clone(), missing.clone()].map(|path| (path.clone(), path));
+        watch.update(here.into_iter());
+        assert_eq!(watch.dead(), vec![missing.clone()]);
+
+        fs::remove_dir(&deleted).unwrap();
+        let start = Instant::now();
+        while watch.dead().len() < 2 && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(watch.dead(), [deleted, missing]);
+    }
+
+    #[test]
+    fn reports_bookmarks_as_stored() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let home = root.join("home");
+        fs::create_dir(&home).unwrap();
+
+        let mut watch = Watch::new().unwrap();
+        let bookmarks = [
+            (PathBuf::from("~"), home),
+            (PathBuf::from("~/gone"), root.join("gone")),
+        ];
+        watch.update(bookmarks.into_iter());
+        assert_eq!(watch.dead(), [PathBuf::from("~/gone")]);
+    }
+}
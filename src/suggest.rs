@@ -0,0 +1,52 @@
+/// Candidates within a small edit distance of `query`, closest first.
+pub fn suggestions<'a>(query: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let max_distance = (query.chars().count() / 4).max(2);
+
+    let mut close: Vec<_> = candidates
+        .into_iter()
+        .map(|candidate| (levenshtein(query, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+
+    close.sort_by_key(|(distance, _)| *distance);
+    close.into_iter().take(3).map(|(_, c)| c).collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+    }
+
+    #[test]
+    fn closest_first() {
+        let candidates = ["/home/me/project", "/home/me/projects", "/srv/www"];
+
+        assert_eq!(
+            suggestions("/home/me/projcet", candidates),
+            ["/home/me/project", "/home/me/projects"]
+        );
+        assert!(suggestions("/tmp", candidates).is_empty());
+    }
+}
@@ -0,0 +1,320 @@
+//! `pathmarks sync`, sharing the bookmarks of every profile between machines
+//! through a git repository in the data directory.
+//!
+//! Local changes are committed, then merged with those of the `origin`
+//! remote bookmark by bookmark rather than line by line, and pushed. Only
+//! the stores are committed; undo history, locks and logs stay local.
+//!
+//! Without git, configured [`SyncCommands`] copy the store of the profile from and
+//! to wherever it is shared, and the same merge runs between them.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::Deserialize;
+use tracing::info;
+
+use crate::command::{from_template, run};
+use crate::error::{AppError, AppResult};
+use crate::merge::newest;
+use crate::store::{Bookmark, Encryption, Store, TextStore, decode};
+
+const STORE: &str = "bookmarks.txt";
+
+const GITIGNORE: &str = "\
+# Written by pathmarks sync, which only shares the bookmarks.
+*
+!*/
+!bookmarks.txt
+!.gitignore
+";
+
+/// Syncs the stores in `dir` with the `origin` remote, which is set to
+/// `remote` first when given.
+pub fn sync(dir: &Path, remote: Option<&str>, encryption: Option<&Encryption>) -> AppResult<()> {
+    if !dir.join(".git").exists() {
+        git(dir, &["init", "--quiet", "--initial-branch=main"])?;
+    }
+    fs::write(dir.join(".gitignore"), GITIGNORE)?;
+
+    let has_origin = git(dir, &["remote"])?.lines().any(|r| r == "origin");
+    match remote {
+        Some(url) if has_origin => git(dir, &["remote", "set-url", "origin", url])?,
+        Some(url) => git(dir, &["remote", "add", "origin", url])?,
+        None => String::new(),
+    };
+
+    git(dir, &["add", "--all"])?;
+    if !succeeds(dir, &["diff", "--cached", "--quiet"]) {
+        git(dir, &["commit", "--quiet", "--message", "Update bookmarks"])?;
+        info!("Committed the local changes");
+    }
+
+    if !has_origin && remote.is_none() {
+        info!("There is no remote to sync with, add one with --remote");
+        return Ok(());
+    }
+
+    let branch = git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+    git(dir, &["fetch", "--quiet", "origin"])?;
+    let upstream = format!("origin/{branch}");
+    if succeeds(dir, &["rev-parse", "--verify", "--quiet", &upstream]) {
+        merge(dir, &upstream, encryption)?;
+    }
+
+    git(
+        dir,
+        &["push", "--quiet", "--set-upstream", "origin", &branch],
+    )?;
+    info!("Pushed to {upstream}");
+    Ok(())
+}
+
+/// Commands copying the store from and to where it is shared, with `{}`
+/// standing for the local copy.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyncCommands {
+    pub pull_cmd: String,
+    pub push_cmd: String,
+}
+
+/// Syncs `store` through `commands`. The pulled copy is merged with the store,
+/// using the copy pushed last time as the base.
+pub fn sync_with_commands(
+    store: &dyn Store,
+    commands: &SyncCommands,
+    encryption: Option<&Encryption>,
+) -> AppResult<()> {
+    let copy = |extension| TextStore {
+        file: store.file().with_extension(extension),
+        encryption: encryption.cloned(),
+    };
+    let (shared, base) = (copy("shared"), copy("synced"));
+    let read = |copy: &TextStore| match copy.file.exists() {
+        true => copy.read(),
+        false => Ok(Vec::new()),
+    };
+
+    // Nothing is shared yet when the pull leaves no copy behind.
+    if shared.file.exists() {
+        fs::remove_file(&shared.file)?;
+    }
+    run(from_template(&commands.pull_cmd, &shared.file)?)?;
+    let merged = merge_bookmarks(&read(&base)?, store.read()?, read(&shared)?);
+    store.write(&merged)?;
+
+    shared.write(&merged)?;
+    run(from_template(&commands.push_cmd, &shared.file)?)?;
+    fs::rename(&shared.file, &base.file)?;
+    info!("Synced {} bookmarks", merged.len());
+    Ok(())
+}
+
+fn merge(dir: &Path, upstream: &str, encryption: Option<&Encryption>) -> AppResult<()> {
+    let head = git(dir, &["rev-parse", "HEAD"])?;
+    let theirs = git(dir, &["rev-parse", upstream])?;
+    // Fails for unrelated histories, like the first sync of a machine.
+    let base = succeeds(dir, &["merge-base", "HEAD", upstream])
+        .then(|| git(dir, &["merge-base", "HEAD", upstream]))
+        .transpose()?;
+
+    if base.as_ref() == Some(&theirs) {
+        return Ok(());
+    }
+    if base.as_ref() == Some(&head) {
+        git(dir, &["merge", "--quiet", "--ff-only", upstream])?;
+        info!("Fast-forwarded to {upstream}");
+        return Ok(());
+    }
+
+    let mut files = store_files(dir, "HEAD")?;
+    files.extend(store_files(dir, upstream)?);
+    files.sort();
+    files.dedup();
+
+    // The merge commit gets the trees merged below, not git's.
+    git(
+        dir,
+        &[
+            "merge",
+            "--quiet",
+            "--no-commit",
+            "--strategy=ours",
+            "--allow-unrelated-histories",
+            upstream,
+        ],
+    )?;
+    for file in files {
+        let read = |rev: Option<&str>| match rev {
+            Some(rev) => decode(blob(dir, rev, &file)?, encryption),
+            None => Ok(Vec::new()),
+        };
+        let merged = merge_bookmarks(
+            &read(base.as_deref())?,
+            read(Some("HEAD"))?,
+            read(Some(upstream))?,
+        );
+
+        let file = dir.join(&file);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let store = TextStore {
+            file,
+            encryption: encryption.cloned(),
+        };
+        store.write(&merged)?;
+    }
+    git(dir, &["add", "--all"])?;
+    git(dir, &["commit", "--quiet", "--no-edit"])?;
+    info!("Merged the changes of {upstream}");
+    Ok(())
+}
+
+/// Bookmarks of both sides, without the ones a side removed since `base`.
+/// A bookmark on both sides has the metadata of the side that used it last.
+fn merge_bookmarks(
+    base: &[Bookmark],
+    ours: Vec<Bookmark>,
+    mut theirs: Vec<Bookmark>,
+) -> Vec<Bookmark> {
+    let in_base = |b: &Bookmark| base.iter().any(|old| old.path == b.path);
+    let mut merged = Vec::new();
+
+    for bookmark in ours {
+        match theirs.iter().position(|b| b.path == bookmark.path) {
+            Some(i) => merged.push(newest(bookmark, theirs.remove(i))),
+            None if in_base(&bookmark) => {}
+            None => merged.push(bookmark),
+        }
+    }
+    merged.extend(theirs.into_iter().filter(|b| !in_base(b)));
+
+    merged
+}
+
+/// The stores in the tree of `rev`, relative to the repository.
+fn store_files(dir: &Path, rev: &str) -> AppResult<Vec<String>> {
+    let files = git(dir, &["ls-tree", "-r", "--name-only", rev])?;
+    Ok(files
+        .lines()
+        .filter(|file| {
+            Path::new(file)
+                .file_name()
+                .is_some_and(|name| name == STORE)
+        })
+        .map(String::from)
+        .collect())
+}
+
+/// The content of `file` at `rev`, empty when it is not there.
+fn blob(dir: &Path, rev: &str, file: &str) -> AppResult<Vec<u8>> {
+    let object = format!("{rev}:{file}");
+    if !succeeds(dir, &["cat-file", "-e", &object]) {
+        return Ok(Vec::new());
+    }
+
+    let output = command(dir, &["show", &object]).output()?;
+    if !output.status.success() {
+        return Err(AppError::CommandFailed(
+            "git show".to_string(),
+            output.status,
+        ));
+    }
+    Ok(output.stdout)
+}
+
+fn command(dir: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir).args(args);
+    command
+}
+
+/// Runs git, returning its trimmed output. What it says on stderr is only
+/// shown when it fails, as it talks even with `--quiet`.
+fn git(dir: &Path, args: &[&str]) -> AppResult<String> {
+    let output = command(dir, args).output()?;
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        let name = format!("git {}", args.first().unwrap_or(&""));
+        return Err(AppError::CommandFailed(name, output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Whether git succeeds, without showing anything.
+fn succeeds(dir: &Path, args: &[&str]) -> bool {
+    command(dir, args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn bookmark(path: &str, last_used: u64) -> Bookmark {
+        Bookmark {
+            last_used: Some(last_used),
+            ..Bookmark::new(PathBuf::from(path))
+        }
+    }
+
+    #[test]
+    fn merges_bookmark_by_bookmark() {
+        let base = [bookmark("/kept", 1), bookmark("/ours-removed", 1)];
+        let ours = vec![
+            Bookmark {
+                name: Some("old".to_string()),
+                visits: Some(7),
+                ..bookmark("/kept", 2)
+            },
+            bookmark("/ours-added", 1),
+        ];
+        let theirs = vec![
+            bookmark("/theirs-added", 1),
+            bookmark("/ours-removed", 1),
+            Bookmark {
+                name: Some("new".to_string()),
+                visits: Some(3),
+                ..bookmark("/kept", 5)
+            },
+        ];
+
+        let merged = merge_bookmarks(&base, ours, theirs);
+
+        let paths: Vec<_> = merged.iter().filter_map(|b| b.path.to_str()).collect();
+        assert_eq!(paths, ["/kept", "/ours-added", "/theirs-added"]);
+        assert_eq!(merged[0].name.as_deref(), Some("new"));
+        assert_eq!(merged[0].visits, Some(7));
+    }
+
+    #[test]
+    fn syncs_through_commands() {
+        let temp = tempfile::tempdir().unwrap();
+        let remote = temp.path().join("remote.txt");
+        let store = TextStore {
+            file: temp.path().join("bookmarks.txt"),
+            encryption: None,
+        };
+        let commands = SyncCommands {
+            pull_cmd: format!("cp {} {{}}", remote.display()),
+            push_cmd: format!("cp {{}} {}", remote.display()),
+        };
+        fs::write(store.file.with_extension("synced"), "/removed\n").unwrap();
+        fs::write(&store.file, "/ours\n/removed\n").unwrap();
+        fs::write(&remote, "/theirs\n").unwrap();
+
+        sync_with_commands(&store, &commands, None).unwrap();
+
+        assert_eq!(fs::read_to_string(&store.file).unwrap(), "/ours\n/theirs\n");
+        assert_eq!(fs::read_to_string(&remote).unwrap(), "/ours\n/theirs\n");
+        assert!(!store.file.with_extension("shared").exists());
+    }
+}
@@ -0,0 +1,200 @@
+//! Colors of the picker and `list`, set in the `[theme]` table of the config
+//! and left out with `--color never` or `NO_COLOR`.
+
+use std::env;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::remote::is_remote;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color output to a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Auto => terminal && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Words like `bold red` or `dim italic`, kept as the parameters of the
+/// escape sequence they turn into. Colors are names, `bright-` names,
+/// numbers up to 255 or `#rrggbb`.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Style(String);
+
+impl TryFrom<String> for Style {
+    type Error = String;
+
+    fn try_from(words: String) -> Result<Self, Self::Error> {
+        let codes: Vec<String> = words
+            .split_whitespace()
+            .map(|word| code(word).ok_or_else(|| format!("unknown style `{word}`")))
+            .collect::<Result<_, _>>()?;
+        Ok(Style(codes.join(";")))
+    }
+}
+
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+fn code(word: &str) -> Option<String> {
+    let attribute = match word {
+        "bold" => Some(1),
+        "dim" => Some(2),
+        "italic" => Some(3),
+        "underline" => Some(4),
+        "reverse" => Some(7),
+        _ => None,
+    };
+    if let Some(attribute) = attribute {
+        return Some(attribute.to_string());
+    }
+
+    let color = |name: &str| COLORS.iter().position(|c| *c == name);
+    if let Some(i) = color(word) {
+        return Some((30 + i).to_string());
+    }
+    if let Some(i) = word.strip_prefix("bright-").and_then(color) {
+        return Some((90 + i).to_string());
+    }
+    if let Ok(n) = word.parse::<u8>() {
+        return Some(format!("38;5;{n}"));
+    }
+
+    let hex = word.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(format!(
+        "38;2;{};{};{}",
+        channel(0)?,
+        channel(2)?,
+        channel(4)?
+    ))
+}
+
+impl Style {
+    pub fn paint(&self, text: &str) -> String {
+        if self.0.is_empty() {
+            text.to_string()
+        } else {
+            format!("\x1b[{}m{text}\x1b[0m", self.0)
+        }
+    }
+}
+
+/// Every style is optional, an empty one leaves the text as it is.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Bookmarks listed after the directories of the current one.
+    pub bookmark: Style,
+    /// Directories of the current one.
+    pub dir: Style,
+    /// Bookmarks whose directory does not exist.
+    pub missing: Style,
+    pub pinned: Style,
+    /// Bookmarks on other machines, like `ssh://devbox/home/me`.
+    pub remote: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            bookmark: Style("2;3".to_string()),
+            dir: Style::default(),
+            missing: Style::default(),
+            pinned: Style::default(),
+            remote: Style("36".to_string()),
+        }
+    }
+}
+
+static PLAIN: Theme = Theme {
+    bookmark: Style(String::new()),
+    dir: Style(String::new()),
+    missing: Style(String::new()),
+    pinned: Style(String::new()),
+    remote: Style(String::new()),
+};
+
+impl Theme {
+    /// The style of an entry of the picker or `list`. `after_dirs` tells
+    /// whether a bookmark is listed after directories of the current one.
+    pub fn entry(&self, path: &Path, pinned: bool, dir: bool, after_dirs: bool) -> &Style {
+        // Only look for the directory when it makes a difference.
+        let missing = || !self.missing.0.is_empty() && !dir && !path.exists();
+        if is_remote(path) {
+            &self.remote
+        } else if missing() {
+            &self.missing
+        } else if pinned && !self.pinned.0.is_empty() {
+            &self.pinned
+        } else if dir {
+            &self.dir
+        } else if after_dirs {
+            &self.bookmark
+        } else {
+            &PLAIN.bookmark
+        }
+    }
+}
+
+static SETTINGS: OnceLock<(Theme, ColorChoice)> = OnceLock::new();
+
+/// Sets the theme and when it is used, once at startup.
+pub fn init(theme: Theme, color: ColorChoice) {
+    let _ = SETTINGS.set((theme, color));
+}
+
+/// The theme for output to `stream`, without any styles when colors are off
+/// for it.
+pub fn theme(stream: &impl IsTerminal) -> &'static Theme {
+    match SETTINGS.get() {
+        Some((theme, color)) if color.enabled(stream.is_terminal()) => theme,
+        _ => &PLAIN,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_styles() {
+        let style = |words: &str| Style::try_from(words.to_string());
+
+        assert_eq!(style("dim italic"), Ok(Style("2;3".to_string())));
+        assert_eq!(
+            style("bold bright-red 208 #ff8000"),
+            Ok(Style("1;91;38;5;208;38;2;255;128;0".to_string()))
+        );
+        assert_eq!(style(""), Ok(Style::default()));
+        assert!(style("blinking").is_err());
+        assert!(style("#ff80").is_err());
+
+        assert_eq!(style("red").unwrap().paint("a"), "\x1b[31ma\x1b[0m");
+        assert_eq!(Style::default().paint("a"), "a");
+    }
+
+    #[test]
+    fn never_colors_when_asked_not_to() {
+        assert!(ColorChoice::Always.enabled(false));
+        assert!(!ColorChoice::Never.enabled(true));
+        assert!(!ColorChoice::Auto.enabled(false));
+    }
+}
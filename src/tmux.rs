@@ -0,0 +1,88 @@
+//! `pathmarks tmux`, switching to a tmux window or session for a bookmark,
+//! created in its directory the first time.
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+use crate::command::run;
+use crate::error::{AppError, AppResult};
+use crate::store::Bookmark;
+
+/// The window or session name of `bookmark`: its name, or else the name of
+/// its directory. tmux reads `.` and `:` in targets, so they become `_`.
+pub fn name(bookmark: &Bookmark) -> String {
+    let name = match &bookmark.name {
+        Some(name) => name.clone(),
+        None => bookmark.path.file_name().map_or_else(
+            || bookmark.path.to_string_lossy().into_owned(),
+            |name| name.to_string_lossy().into_owned(),
+        ),
+    };
+    name.replace(['.', ':'], "_")
+}
+
+/// Switches to the window `name`, or the session with `session`, creating it
+/// in `dir` when there is none.
+pub fn switch(dir: &Path, name: &str, session: bool) -> AppResult<()> {
+    let inside = env::var_os("TMUX").is_some_and(|v| !v.is_empty());
+    if !session && !inside {
+        return Err(AppError::NotInTmux);
+    }
+
+    if session {
+        let target = format!("={name}");
+        if !succeeds(tmux(["has-session", "-t", &target])) {
+            let mut new = tmux(["new-session", "-d", "-s", name, "-c"]);
+            new.arg(dir);
+            run(new)?;
+        }
+        let attach = if inside {
+            "switch-client"
+        } else {
+            "attach-session"
+        };
+        return run(tmux([attach, "-t", &target]));
+    }
+
+    let windows = tmux(["list-windows", "-F", "#{window_name}"]).output()?;
+    if String::from_utf8_lossy(&windows.stdout)
+        .lines()
+        .any(|window| window == name)
+    {
+        return run(tmux(["select-window", "-t", &format!(":={name}")]));
+    }
+    let mut new = tmux(["new-window", "-n", name, "-c"]);
+    new.arg(dir);
+    run(new)
+}
+
+fn tmux<const N: usize>(args: [&str; N]) -> Command {
+    let mut command = Command::new("tmux");
+    command.args(args);
+    command
+}
+
+fn succeeds(mut command: Command) -> bool {
+    command.output().is_ok_and(|output| output.status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn names_windows_after_bookmarks() {
+        let bookmark = Bookmark::new(PathBuf::from("/src/pathmarks.rs"));
+        assert_eq!(name(&bookmark), "pathmarks_rs");
+
+        let named = Bookmark {
+            name: Some("SofusA/pathmarks".to_string()),
+            ..bookmark
+        };
+        assert_eq!(name(&named), "SofusA/pathmarks");
+        assert_eq!(name(&Bookmark::new(PathBuf::from("/"))), "/");
+    }
+}
@@ -0,0 +1,378 @@
+//! `pathmarks ui`, a full screen list of the bookmarks to reorder, rename,
+//! tag, pin and delete them, with the contents of the selected directory
+//! beside it.
+
+use std::fs;
+use std::io::{self, IsTerminal, Stderr};
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::widgets::{List, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+
+use crate::age::now;
+use crate::error::{AppError, AppResult};
+use crate::index_renderer::label;
+use crate::store::{Bookmark, Store, validate_tag, validate_value};
+use crate::undo::Journal;
+
+const HELP: &str = "↑↓ select  K J move  r rename  t tags  p pin  d delete  enter print  q quit";
+
+/// A change to the bookmark of a path.
+#[derive(Debug, PartialEq)]
+enum Change {
+    /// Up, when negative, or down by this many places.
+    Move(isize),
+    TogglePin,
+    Name(Option<String>),
+    Tags(Vec<String>),
+    Delete,
+}
+
+/// Applies `change` to the bookmark of `path`, returning where it is
+/// afterwards. Nothing changes when the bookmark is gone.
+fn apply(bookmarks: &mut Vec<Bookmark>, path: &Path, change: Change) -> Option<usize> {
+    let i = bookmarks.iter().position(|b| b.path == path)?;
+    let bookmark = &mut bookmarks[i];
+
+    match change {
+        Change::Move(by) => {
+            let to = i.saturating_add_signed(by).min(bookmarks.len() - 1);
+            let bookmark = bookmarks.remove(i);
+            bookmarks.insert(to, bookmark);
+            return Some(to);
+        }
+        Change::TogglePin => bookmark.pinned = !bookmark.pinned,
+        Change::Name(name) => bookmark.name = name,
+        Change::Tags(tags) => bookmark.tags = tags,
+        Change::Delete => {
+            bookmarks.remove(i);
+            return None;
+        }
+    }
+    Some(i)
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Name,
+    Tags,
+}
+
+enum Mode {
+    Browse,
+    /// Text typed for a field of the selected bookmark.
+    Edit(Field, String),
+    /// Waiting for `y` to delete the selected bookmark.
+    ConfirmDelete,
+}
+
+/// What a key press leads to.
+enum Step {
+    Continue,
+    Quit(Option<PathBuf>),
+}
+
+struct Ui<'a> {
+    store: &'a dyn Store,
+    journal: &'a Journal,
+    /// Where a stored path is on this machine, for the preview.
+    local: &'a dyn Fn(&Path) -> PathBuf,
+    bookmarks: Vec<Bookmark>,
+    selected: usize,
+    /// Where the list is scrolled to.
+    list: ListState,
+    mode: Mode,
+    message: String,
+}
+
+/// Shows the bookmarks until the screen is closed, returning the path chosen
+/// with enter.
+pub fn ui(
+    store: &dyn Store,
+    journal: &Journal,
+    local: &dyn Fn(&Path) -> PathBuf,
+) -> AppResult<Option<PathBuf>> {
+    if !io::stderr().is_terminal() {
+        return Err(AppError::NotInteractive("pathmarks ui"));
+    }
+
+    let mut ui = Ui {
+        store,
+        journal,
+        local,
+        bookmarks: store.read()?,
+        selected: 0,
+        list: ListState::default(),
+        mode: Mode::Browse,
+        message: String::new(),
+    };
+
+    terminal::enable_raw_mode()?;
+    execute!(io::stderr(), EnterAlternateScreen)?;
+    let mut screen = Terminal::new(CrosstermBackend::new(io::stderr()))?;
+    let chosen = ui.run(&mut screen);
+    screen.show_cursor()?;
+    execute!(io::stderr(), LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+
+    chosen
+}
+
+impl Ui<'_> {
+    fn run(
+        &mut self,
+        screen: &mut Terminal<CrosstermBackend<Stderr>>,
+    ) -> AppResult<Option<PathBuf>> {
+        loop {
+            screen.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Step::Quit(chosen) = self.key(key)? {
+                return Ok(chosen);
+            }
+        }
+    }
+
+    fn key(&mut self, key: KeyEvent) -> AppResult<Step> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Ok(Step::Quit(None));
+        }
+        self.message.clear();
+
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => return self.browse(key),
+            Mode::ConfirmDelete => {
+                if key.code == KeyCode::Char('y') {
+                    self.change(Change::Delete)?;
+                }
+            }
+            Mode::Edit(field, mut text) => match key.code {
+                KeyCode::Enter => self.submit(field, &text)?,
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    text.pop();
+                    self.mode = Mode::Edit(field, text);
+                }
+                KeyCode::Char(c) => {
+                    text.push(c);
+                    self.mode = Mode::Edit(field, text);
+                }
+                _ => self.mode = Mode::Edit(field, text),
+            },
+        }
+        Ok(Step::Continue)
+    }
+
+    fn browse(&mut self, key: KeyEvent) -> AppResult<Step> {
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        let Some(bookmark) = self.bookmarks.get(self.selected) else {
+            return Ok(match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => Step::Quit(None),
+                _ => Step::Continue,
+            });
+        };
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Step::Quit(None)),
+            KeyCode::Enter => return Ok(Step::Quit(Some((self.local)(&bookmark.path)))),
+            KeyCode::Up if shift => self.change(Change::Move(-1))?,
+            KeyCode::Down if shift => self.change(Change::Move(1))?,
+            KeyCode::Char('K') => self.change(Change::Move(-1))?,
+            KeyCode::Char('J') => self.change(Change::Move(1))?,
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.bookmarks.len() - 1)
+            }
+            KeyCode::Home | KeyCode::Char('g') => self.selected = 0,
+            KeyCode::End | KeyCode::Char('G') => self.selected = self.bookmarks.len() - 1,
+            KeyCode::Char('p') => self.change(Change::TogglePin)?,
+            KeyCode::Char('r') => {
+                let name = bookmark.name.clone().unwrap_or_default();
+                self.mode = Mode::Edit(Field::Name, name);
+            }
+            KeyCode::Char('t') => self.mode = Mode::Edit(Field::Tags, bookmark.tags.join(" ")),
+            KeyCode::Char('d') | KeyCode::Delete => self.mode = Mode::ConfirmDelete,
+            _ => {}
+        }
+        Ok(Step::Continue)
+    }
+
+    /// Saves the text typed for `field`, or keeps editing with a message
+    /// when it is not valid.
+    fn submit(&mut self, field: Field, text: &str) -> AppResult<()> {
+        let text = text.trim();
+        let change = match field {
+            Field::Name => validate_value(text)
+                .map(|()| Change::Name((!text.is_empty()).then(|| text.to_string()))),
+            Field::Tags => text
+                .split_whitespace()
+                .map(|tag| validate_tag(tag).map(|()| tag.to_string()))
+                .collect::<AppResult<_>>()
+                .map(Change::Tags),
+        };
+
+        match change {
+            Ok(change) => self.change(change),
+            Err(err) => {
+                self.message = err.to_string();
+                self.mode = Mode::Edit(field, text.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// Applies `change` to the selected bookmark in the store, keeping
+    /// changes other processes made in the meantime.
+    fn change(&mut self, change: Change) -> AppResult<()> {
+        let Some(path) = self.bookmarks.get(self.selected).map(|b| b.path.clone()) else {
+            return Ok(());
+        };
+        let delete = change == Change::Delete;
+
+        let _lock = self.store.lock()?;
+        let mut bookmarks = self.store.read()?;
+        let moved_to = apply(&mut bookmarks, &path, change);
+        if delete {
+            self.journal
+                .write(self.store, "remove", now(), &bookmarks)?;
+            self.message = format!("Deleted {}, `pathmarks undo` restores it", path.display());
+        } else {
+            self.store.write(&bookmarks)?;
+        }
+
+        self.selected = moved_to
+            .unwrap_or(self.selected)
+            .min(bookmarks.len().saturating_sub(1));
+        self.bookmarks = bookmarks;
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status_line] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        let [list, preview_pane] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Fill(1)])
+                .spacing(1)
+                .areas(main);
+
+        if self.bookmarks.is_empty() {
+            frame.render_widget(Paragraph::new("No bookmarks"), list);
+        } else {
+            let local = self.local;
+            let labels = self
+                .bookmarks
+                .iter()
+                .map(|b| label(b, local(&b.path).is_file()));
+            let labels = List::new(labels).highlight_symbol("> ");
+            self.list.select(Some(self.selected));
+            frame.render_stateful_widget(labels, list, &mut self.list);
+        }
+
+        if let Some(bookmark) = self.bookmarks.get(self.selected) {
+            let preview = preview(bookmark, &(self.local)(&bookmark.path));
+            frame.render_widget(Paragraph::new(preview.join("\n")), preview_pane);
+        }
+
+        let status = match &self.mode {
+            Mode::Browse if self.message.is_empty() => HELP.to_string(),
+            Mode::Browse => self.message.clone(),
+            Mode::ConfirmDelete => "Delete this bookmark? y to confirm".to_string(),
+            Mode::Edit(field, text) => {
+                let prompt = match field {
+                    Field::Name => "Name",
+                    Field::Tags => "Tags",
+                };
+                format!("{prompt}: {text}_  {}", self.message)
+            }
+        };
+        frame.render_widget(Paragraph::new(status), status_line);
+    }
+}
+
+/// The details of a bookmark followed by what is in its directory,
+/// directories first.
+fn preview(bookmark: &Bookmark, local: &Path) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(name) = &bookmark.name {
+        lines.push(format!("Name: {name}"));
+    }
+    if !bookmark.tags.is_empty() {
+        lines.push(format!("Tags: {}", bookmark.tags.join(" ")));
+    }
+    if let Some(note) = &bookmark.note {
+        lines.push(format!("Note: {note}"));
+    }
+    if let Some(visits) = bookmark.visits {
+        lines.push(format!("Visits: {visits}"));
+    }
+    if !lines.is_empty() {
+        lines.push(String::new());
+    }
+
+    let Ok(entries) = fs::read_dir(local) else {
+        lines.push("Directory does not exist".to_string());
+        return lines;
+    };
+    let mut entries: Vec<_> = entries
+        .flatten()
+        .map(|entry| {
+            let dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            let name = entry.file_name().to_string_lossy().into_owned();
+            (!dir, if dir { format!("{name}/") } else { name })
+        })
+        .collect();
+    entries.sort();
+    lines.extend(entries.into_iter().map(|(_, name)| name));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(bookmarks: &[Bookmark]) -> Vec<&str> {
+        bookmarks.iter().filter_map(|b| b.path.to_str()).collect()
+    }
+
+    #[test]
+    fn changes_follow_the_bookmark() {
+        let mut bookmarks: Vec<_> = ["/a", "/b", "/c"]
+            .into_iter()
+            .map(|p| Bookmark::new(PathBuf::from(p)))
+            .collect();
+
+        assert_eq!(
+            apply(&mut bookmarks, Path::new("/a"), Change::Move(1)),
+            Some(1)
+        );
+        assert_eq!(paths(&bookmarks), ["/b", "/a", "/c"]);
+        assert_eq!(
+            apply(&mut bookmarks, Path::new("/c"), Change::Move(1)),
+            Some(2)
+        );
+        assert_eq!(
+            apply(&mut bookmarks, Path::new("/b"), Change::Move(-1)),
+            Some(0)
+        );
+
+        let name = Change::Name(Some("work".to_string()));
+        assert_eq!(apply(&mut bookmarks, Path::new("/c"), name), Some(2));
+        assert_eq!(bookmarks[2].name.as_deref(), Some("work"));
+
+        assert_eq!(apply(&mut bookmarks, Path::new("/a"), Change::Delete), None);
+        assert_eq!(paths(&bookmarks), ["/b", "/c"]);
+        assert_eq!(
+            apply(&mut bookmarks, Path::new("/a"), Change::TogglePin),
+            None
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! A journal of the store as it was before destructive operations, so they
+//! can be undone.
+//!
+//! Every entry is a text store in the `undo` directory next to the store,
+//! named `<sequence>-<unix time>-<operation>.txt` and encrypted like the
+//! store when encryption is configured.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppResult;
+use crate::store::{Bookmark, Encryption, Store, TextStore};
+
+const MAX_ENTRIES: usize = 10;
+
+pub struct Journal {
+    dir: PathBuf,
+    encryption: Option<Encryption>,
+}
+
+pub struct Entry {
+    file: PathBuf,
+    sequence: u64,
+    pub time: u64,
+    pub operation: String,
+}
+
+impl Journal {
+    pub fn new(store_file: &Path, encryption: Option<Encryption>) -> Self {
+        Self {
+            dir: store_file.with_file_name("undo"),
+            encryption,
+        }
+    }
+
+    /// Records `bookmarks` as the state before `operation`, forgetting the
+    /// oldest entries beyond [`MAX_ENTRIES`].
+    fn record(&self, operation: &str, time: u64, bookmarks: &[Bookmark]) -> AppResult<()> {
+        let entries = self.entries();
+        let sequence = entries.first().map_or(0, |e| e.sequence + 1);
+
+        let file = self
+            .dir
+            .join(format!("{sequence:06}-{time}-{operation}.txt"));
+        self.text_store(file).write(bookmarks)?;
+
+        for old in entries.iter().skip(MAX_ENTRIES - 1) {
+            fs::remove_file(&old.file)?;
+        }
+        Ok(())
+    }
+
+    /// Writes `bookmarks` to `store`, recording the previous state first.
+    pub fn write(
+        &self,
+        store: &dyn Store,
+        operation: &str,
+        time: u64,
+        bookmarks: &[Bookmark],
+    ) -> AppResult<()> {
+        self.record(operation, time, &store.read()?)?;
+        store.write(bookmarks)
+    }
+
+    /// Entries, newest first.
+    pub fn entries(&self) -> Vec<Entry> {
+        let mut entries: Vec<_> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let file = entry.path();
+                let stem = file.file_stem()?.to_str()?.to_string();
+                let mut parts = stem.splitn(3, '-');
+                Some(Entry {
+                    sequence: parts.next()?.parse().ok()?,
+                    time: parts.next()?.parse().ok()?,
+                    operation: parts.next()?.to_string(),
+                    file,
+                })
+            })
+            .collect();
+
+        entries.sort_by_key(|e| std::cmp::Reverse(e.sequence));
+        entries
+    }
+
+    pub fn read(&self, entry: &Entry) -> AppResult<Vec<Bookmark>> {
+        self.text_store(entry.file.clone()).read()
+    }
+
+    pub fn forget(&self, entry: Entry) -> AppResult<()> {
+        fs::remove_file(entry.file)?;
+        Ok(())
+    }
+
+    fn text_store(&self, file: PathBuf) -> TextStore {
+        TextStore {
+            file,
+            encryption: self.encryption.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_newest_entries() {
+        let temp = tempfile::tempdir().unwrap();
+        let journal = Journal::new(&temp.path().join("bookmarks.txt"), None);
+
+        for i in 0..MAX_ENTRIES + 2 {
+            let bookmarks = [Bookmark::new(PathBuf::from(format!("/{i}")))];
+            journal.record("remove", i as u64, &bookmarks).unwrap();
+        }
+
+        let entries = journal.entries();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].time, MAX_ENTRIES as u64 + 1);
+        assert_eq!(entries[0].operation, "remove");
+        assert_eq!(
+            journal.read(&entries[0]).unwrap(),
+            [Bookmark::new(PathBuf::from(format!(
+                "/{}",
+                MAX_ENTRIES + 1
+            )))]
+        );
+    }
+}